@@ -0,0 +1,104 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Stops requests from being sent to a backend that keeps failing.
+///
+/// After `failure_threshold` consecutive failures the circuit opens and
+/// requests fail immediately. Once `cooldown` has passed a single request is
+/// let through as a probe: if it succeeds the circuit closes, and if it fails
+/// the circuit stays open for another cooldown. A probe that never reports
+/// back (for instance because it was cancelled) is replaced by another after
+/// a further cooldown.
+pub(crate) struct CircuitBreaker {
+    failure_threshold: u32,
+    cooldown: Duration,
+    state: Mutex<State>,
+}
+
+#[derive(Default)]
+struct State {
+    consecutive_failures: u32,
+    open_until: Option<Instant>,
+}
+
+impl CircuitBreaker {
+    pub fn new(failure_threshold: u32, cooldown: Duration) -> Self {
+        Self {
+            failure_threshold: failure_threshold.max(1),
+            cooldown,
+            state: Default::default(),
+        }
+    }
+
+    /// Returns whether a request may be sent now.
+    pub fn allow_request(&self) -> bool {
+        let mut state = self.state.lock().unwrap();
+        match state.open_until {
+            None => true,
+            Some(open_until) => {
+                let now = Instant::now();
+                if now < open_until {
+                    return false;
+                }
+                // Half-open: let this request probe the backend and keep
+                // everything else out until it reports back.
+                state.open_until = Some(now + self.cooldown);
+                true
+            }
+        }
+    }
+
+    pub fn record_success(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.consecutive_failures = 0;
+        state.open_until = None;
+    }
+
+    pub fn record_failure(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.consecutive_failures = state.consecutive_failures.saturating_add(1);
+        if state.consecutive_failures >= self.failure_threshold {
+            if state.open_until.is_none() {
+                tracing::warn!(
+                    "LLM backend failed {} consecutive requests; pausing requests for {:?}",
+                    state.consecutive_failures,
+                    self.cooldown
+                );
+            }
+            state.open_until = Some(Instant::now() + self.cooldown);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn opens_after_consecutive_failures() {
+        let breaker = CircuitBreaker::new(3, Duration::from_secs(60));
+        for _ in 0..2 {
+            breaker.record_failure();
+            assert!(breaker.allow_request());
+        }
+        breaker.record_success();
+        for _ in 0..2 {
+            breaker.record_failure();
+        }
+        assert!(breaker.allow_request());
+        breaker.record_failure();
+        assert!(!breaker.allow_request());
+    }
+
+    #[test]
+    fn half_open_probe_closes_or_reopens() {
+        let breaker = CircuitBreaker::new(1, Duration::ZERO);
+        breaker.record_failure();
+        assert!(breaker.allow_request());
+        breaker.record_failure();
+        assert!(breaker.state.lock().unwrap().open_until.is_some());
+        assert!(breaker.allow_request());
+        breaker.record_success();
+        assert!(breaker.state.lock().unwrap().open_until.is_none());
+    }
+}
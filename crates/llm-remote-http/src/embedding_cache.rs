@@ -0,0 +1,102 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+
+/// Remembers the embeddings of recently embedded inputs, evicting the least
+/// recently used once `capacity` entries are held.
+pub(crate) struct EmbeddingCache {
+    capacity: usize,
+    state: Mutex<State>,
+}
+
+type Key = (String, String);
+
+#[derive(Default)]
+struct State {
+    entries: HashMap<Key, Entry>,
+    /// Keys by the tick at which they were last used, oldest first.
+    recency: BTreeMap<u64, Key>,
+    tick: u64,
+}
+
+struct Entry {
+    embedding: Vec<f32>,
+    last_used: u64,
+}
+
+impl State {
+    fn touch(&mut self, key: &Key) -> Option<&Entry> {
+        self.tick += 1;
+        let tick = self.tick;
+        let entry = self.entries.get_mut(key)?;
+        self.recency.remove(&entry.last_used);
+        entry.last_used = tick;
+        self.recency.insert(tick, key.clone());
+        Some(entry)
+    }
+}
+
+impl EmbeddingCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            state: Default::default(),
+        }
+    }
+
+    /// Returns the cached embedding of `input` under `model`, if any.
+    pub fn get(&self, model: &str, input: &str) -> Option<Vec<f32>> {
+        let key = (model.to_owned(), input.to_owned());
+        let mut state = self.state.lock().unwrap();
+        state.touch(&key).map(|entry| entry.embedding.clone())
+    }
+
+    pub fn insert(&self, model: &str, input: &str, embedding: Vec<f32>) {
+        if self.capacity == 0 {
+            return;
+        }
+        let key = (model.to_owned(), input.to_owned());
+        let mut state = self.state.lock().unwrap();
+        if state.touch(&key).is_some() {
+            state.entries.get_mut(&key).unwrap().embedding = embedding;
+            return;
+        }
+        if state.entries.len() >= self.capacity {
+            if let Some((_, oldest)) = state.recency.pop_first() {
+                state.entries.remove(&oldest);
+            }
+        }
+        let last_used = state.tick;
+        state.recency.insert(last_used, key.clone());
+        state.entries.insert(
+            key,
+            Entry {
+                embedding,
+                last_used,
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn evicts_least_recently_used() {
+        let cache = EmbeddingCache::new(2);
+        cache.insert("m", "a", vec![1.0]);
+        cache.insert("m", "b", vec![2.0]);
+        assert_eq!(Some(vec![1.0]), cache.get("m", "a"));
+        cache.insert("m", "c", vec![3.0]);
+        assert_eq!(None, cache.get("m", "b"));
+        assert_eq!(Some(vec![1.0]), cache.get("m", "a"));
+        assert_eq!(Some(vec![3.0]), cache.get("m", "c"));
+    }
+
+    #[test]
+    fn entries_are_per_model() {
+        let cache = EmbeddingCache::new(4);
+        cache.insert("m1", "a", vec![1.0]);
+        assert_eq!(None, cache.get("m2", "a"));
+    }
+}
@@ -1,22 +1,198 @@
+mod circuit_breaker;
+mod embedding_cache;
+mod openai;
+mod unix;
+
 use anyhow::Result;
+use base64::{prelude::BASE64_STANDARD, Engine};
+use circuit_breaker::CircuitBreaker;
+use embedding_cache::EmbeddingCache;
+use flate2::{write::GzEncoder, Compression};
+use futures::{Stream, StreamExt};
+use once_cell::sync::OnceCell;
+use rand::Rng;
 use reqwest::{
-    header::{HeaderMap, HeaderValue},
-    Client, Url,
+    header::{HeaderMap, HeaderName, HeaderValue, RETRY_AFTER, USER_AGENT},
+    Client, RequestBuilder, Response, StatusCode, Url,
 };
+pub use reqwest::{Certificate, Identity};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use spin_core::async_trait;
-use spin_llm::LlmEngine;
+use spin_llm::{
+    DetailedInferencingResult, InferencingChunk, InferencingStream, LlmEngine,
+    PartialInferencingError, DEFAULT_INFERENCING_PARAMS,
+};
 use spin_world::v2::llm::{self as wasi_llm};
-use tracing::{instrument, Level};
+use std::collections::{HashMap, VecDeque};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tracing::{field::Empty, instrument, Level};
 
 #[derive(Clone)]
 pub struct RemoteHttpLlmEngine {
-    auth_token: String,
+    auth_source: AuthSource,
     url: Url,
-    client: Option<Client>,
+    fallback_url: Option<Url>,
+    api_flavor: ApiFlavor,
+    auth_scheme: AuthScheme,
+    propagate_trace_context: bool,
+    compress_requests: bool,
+    infer_path: Option<String>,
+    embed_path: Option<String>,
+    chat_path: String,
+    models_path: Option<String>,
+    health_path: String,
+    stop: Vec<String>,
+    seed: Option<u64>,
+    model_defaults: HashMap<String, ModelDefaults>,
+    /// Sent as `x-request-id` in place of a new random ID for each request.
+    request_id: Option<String>,
+    presence_penalty: Option<f32>,
+    frequency_penalty: Option<f32>,
+    batching: bool,
+    expected_embedding_dim: Option<usize>,
+    normalize_embeddings: bool,
+    /// Shared between clones, like the connection pool.
+    embedding_cache: Option<Arc<EmbeddingCache>>,
+    /// Limits the number of requests in flight across all clones.
+    concurrency_limit: Option<Arc<Semaphore>>,
+    /// Shared between clones so that they all stop sending requests to a
+    /// failing backend.
+    circuit_breaker: Option<Arc<CircuitBreaker>>,
+    timeout: Option<Duration>,
+    connect_timeout: Option<Duration>,
+    proxy: Option<String>,
+    user_agent: String,
+    extra_root_certs: Vec<Certificate>,
+    identity: Option<Identity>,
+    accept_invalid_certs: bool,
+    max_retries: u32,
+    retry_backoff: Duration,
+    /// Shared between clones so that they pool connections to the backend.
+    client: Arc<OnceCell<Client>>,
+    /// Set if the engine was given a `unix://` URL, in which case requests
+    /// are built against a placeholder `url` and sent over the socket.
+    unix_client: Option<unix::UnixClient>,
+}
+
+/// The request and response schema spoken by the remote backend.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+pub enum ApiFlavor {
+    /// Spin's own `/infer` and `/embed` API.
+    #[default]
+    #[serde(rename = "spin")]
+    SpinCustom,
+    /// The OpenAI `/v1/completions` and `/v1/embeddings` API, as implemented
+    /// by servers such as vLLM, LocalAI and Ollama.
+    #[serde(rename = "openai")]
+    OpenAi,
+}
+
+impl ApiFlavor {
+    fn infer_path(self) -> &'static str {
+        match self {
+            Self::SpinCustom => "/infer",
+            Self::OpenAi => "/v1/completions",
+        }
+    }
+
+    fn embed_path(self) -> &'static str {
+        match self {
+            Self::SpinCustom => "/embed",
+            Self::OpenAi => "/v1/embeddings",
+        }
+    }
+
+    fn models_path(self) -> &'static str {
+        match self {
+            Self::SpinCustom => "/models",
+            Self::OpenAi => "/v1/models",
+        }
+    }
+}
+
+/// How the engine's auth token is presented to the backend.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AuthScheme {
+    /// `Authorization: bearer <token>`.
+    #[default]
+    Bearer,
+    /// The token is sent verbatim in the named header, e.g. Azure OpenAI's
+    /// `api-key`.
+    ApiKeyHeader(String),
+    /// `Authorization: Basic <base64(token)>`, where the token is in
+    /// `user:password` form.
+    Basic,
+    /// No credentials are sent.
+    None,
 }
 
+/// The path to which [`RemoteHttpLlmEngine::infer_chat`] sends requests unless
+/// overridden with [`RemoteHttpLlmEngine::with_chat_path`].
+pub const DEFAULT_CHAT_PATH: &str = "/v1/chat/completions";
+
+/// The path queried by [`RemoteHttpLlmEngine::health`] unless overridden with
+/// [`RemoteHttpLlmEngine::with_health_path`].
+pub const DEFAULT_HEALTH_PATH: &str = "/health";
+
+/// The `User-Agent` sent to the backend unless overridden with
+/// [`RemoteHttpLlmEngine::with_user_agent`].
+pub const DEFAULT_USER_AGENT: &str = concat!("spin-llm-remote-http/", env!("CARGO_PKG_VERSION"));
+
+/// The header carrying the ID that identifies each request in the backend's
+/// logs and in errors.
+const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// The smallest request body that is compressed when request compression is
+/// enabled. Compressing smaller bodies gains little.
+const COMPRESSION_THRESHOLD: usize = 4 * 1024;
+
+/// Where the engine gets its auth token from. Tokens from the environment or a
+/// file are read afresh for every request, so that they can be rotated
+/// without restarting Spin.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AuthSource {
+    /// A fixed token.
+    Static(String),
+    /// The value of the named environment variable.
+    Env(String),
+    /// The contents of the file at the given path, less surrounding whitespace.
+    File(PathBuf),
+}
+
+impl AuthSource {
+    fn resolve(&self) -> Result<String, wasi_llm::Error> {
+        match self {
+            Self::Static(token) => Ok(token.clone()),
+            Self::Env(name) => std::env::var(name).map_err(|err| {
+                wasi_llm::Error::RuntimeError(format!(
+                    "Failed to read auth token from environment variable {name}: {err}"
+                ))
+            }),
+            Self::File(path) => std::fs::read_to_string(path)
+                .map(|token| token.trim().to_owned())
+                .map_err(|err| {
+                    wasi_llm::Error::RuntimeError(format!(
+                        "Failed to read auth token from {}: {err}",
+                        path.display()
+                    ))
+                }),
+        }
+    }
+}
+
+/// The base delay before retrying a failed request, used unless overridden
+/// with [`RemoteHttpLlmEngine::with_retries`].
+pub const DEFAULT_RETRY_BACKOFF: Duration = Duration::from_millis(200);
+
+/// The longest delay between retries, however many attempts have been made.
+const MAX_RETRY_BACKOFF: Duration = Duration::from_secs(30);
+
 #[derive(Serialize)]
 #[serde(rename_all(serialize = "camelCase"))]
 struct InferRequestBodyParams {
@@ -26,6 +202,14 @@ struct InferRequestBodyParams {
     temperature: f32,
     top_k: u32,
     top_p: f32,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    stop: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    seed: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    presence_penalty: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    frequency_penalty: Option<f32>,
 }
 
 #[derive(Deserialize)]
@@ -39,6 +223,29 @@ struct InferUsage {
 struct InferResponseBody {
     text: String,
     usage: InferUsage,
+    #[serde(default, rename = "finishReason")]
+    finish_reason: Option<String>,
+}
+
+impl From<InferResponseBody> for wasi_llm::InferencingResult {
+    fn from(val: InferResponseBody) -> Self {
+        wasi_llm::InferencingResult {
+            text: val.text,
+            usage: wasi_llm::InferencingUsage {
+                prompt_token_count: val.usage.prompt_token_count,
+                generated_token_count: val.usage.generated_token_count,
+            },
+        }
+    }
+}
+
+/// A single server-sent event in a streaming inference response.
+#[derive(Deserialize)]
+struct InferStreamEvent {
+    #[serde(default)]
+    text: Option<String>,
+    #[serde(default)]
+    usage: Option<InferUsage>,
 }
 
 #[derive(Deserialize)]
@@ -53,129 +260,2396 @@ struct EmbeddingResponseBody {
     usage: EmbeddingUsage,
 }
 
+/// Inferencing parameters for a model, set with
+/// [`RemoteHttpLlmEngine::with_model_defaults`].
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ModelDefaults {
+    pub max_tokens: Option<u32>,
+    pub repeat_penalty: Option<f32>,
+    pub repeat_penalty_last_n_token_count: Option<u32>,
+    pub temperature: Option<f32>,
+    pub top_k: Option<u32>,
+    pub top_p: Option<f32>,
+}
+
+impl ModelDefaults {
+    /// Replaces the parameters in `params` that these defaults set.
+    fn apply(&self, params: wasi_llm::InferencingParams) -> wasi_llm::InferencingParams {
+        wasi_llm::InferencingParams {
+            max_tokens: self.max_tokens.unwrap_or(params.max_tokens),
+            repeat_penalty: self.repeat_penalty.unwrap_or(params.repeat_penalty),
+            repeat_penalty_last_n_token_count: self
+                .repeat_penalty_last_n_token_count
+                .unwrap_or(params.repeat_penalty_last_n_token_count),
+            temperature: self.temperature.unwrap_or(params.temperature),
+            top_k: self.top_k.unwrap_or(params.top_k),
+            top_p: self.top_p.unwrap_or(params.top_p),
+        }
+    }
+}
+
+/// The result of [`RemoteHttpLlmEngine::infer_raw`].
+pub struct RawInferencingResult {
+    pub result: DetailedInferencingResult,
+    /// The response body exactly as the backend sent it.
+    pub body: serde_json::Value,
+}
+
+/// A message in a conversation passed to [`RemoteHttpLlmEngine::infer_chat`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChatMessage {
+    /// Who the message is from, usually `system`, `user` or `assistant`.
+    pub role: String,
+    pub content: String,
+}
+
+impl ChatMessage {
+    pub fn new(role: impl Into<String>, content: impl Into<String>) -> Self {
+        Self {
+            role: role.into(),
+            content: content.into(),
+        }
+    }
+}
+
+/// A model served by the remote backend, as reported by
+/// [`RemoteHttpLlmEngine::list_models`].
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+pub struct ModelInfo {
+    /// The name to pass as the inferencing or embedding model.
+    pub id: String,
+    /// The maximum number of tokens in the model's context window, if the
+    /// backend reports it.
+    #[serde(default)]
+    pub context_length: Option<u32>,
+}
+
+/// Backends return either a bare array of models or, like OpenAI, an object
+/// with the array in its `data` field.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ModelsResponseBody {
+    List(Vec<ModelInfo>),
+    Wrapped { data: Vec<ModelInfo> },
+}
+
 #[async_trait]
 impl LlmEngine for RemoteHttpLlmEngine {
-    #[instrument(name = "spin_llm_remote_http.infer", skip(self, prompt), err(level = Level::INFO), fields(otel.kind = "client"))]
     async fn infer(
         &mut self,
         model: wasi_llm::InferencingModel,
         prompt: String,
         params: wasi_llm::InferencingParams,
     ) -> Result<wasi_llm::InferencingResult, wasi_llm::Error> {
-        let client = self.client.get_or_insert_with(Default::default);
+        let detailed = self.infer_detailed(model, prompt, params).await?;
+        Ok(detailed.result)
+    }
 
-        let mut headers = HeaderMap::new();
-        headers.insert(
-            "authorization",
-            HeaderValue::from_str(&format!("bearer {}", self.auth_token)).map_err(|_| {
-                wasi_llm::Error::RuntimeError("Failed to create authorization header".to_string())
-            })?,
-        );
-        spin_telemetry::inject_trace_context(&mut headers);
+    fn default_params(&self, model: &str) -> wasi_llm::InferencingParams {
+        match self.model_defaults.get(model) {
+            Some(defaults) => defaults.apply(DEFAULT_INFERENCING_PARAMS),
+            None => DEFAULT_INFERENCING_PARAMS,
+        }
+    }
+
+    async fn infer_detailed(
+        &mut self,
+        model: wasi_llm::InferencingModel,
+        prompt: String,
+        params: wasi_llm::InferencingParams,
+    ) -> Result<DetailedInferencingResult, wasi_llm::Error> {
+        let raw = self.infer_raw(model, prompt, params).await?;
+        Ok(raw.result)
+    }
+
+    #[instrument(name = "spin_llm_remote_http.infer_stream", skip(self, prompt), err(level = Level::INFO), fields(otel.kind = "client", llm.endpoint = Empty))]
+    async fn infer_stream(
+        &mut self,
+        model: wasi_llm::InferencingModel,
+        prompt: String,
+        params: wasi_llm::InferencingParams,
+    ) -> Result<InferencingStream, wasi_llm::Error> {
+        let request_id = self.request_id();
+        let result: Result<_, wasi_llm::Error> = async {
+            let mut headers = self.request_headers(&request_id)?;
+            headers.insert("accept", HeaderValue::from_static("text/event-stream"));
+            let client = self.client()?;
+
+            let body = self.infer_request_body(&model, &prompt, &params, true)?;
+            // Held until the response has been streamed in full.
+            let permit = self.acquire_permit().await?;
+            let path = self.infer_path();
+            let infer_url = self.endpoint_url(path)?;
+            tracing::info!("Sending remote streaming inference request to {infer_url}");
+
+            let request = client
+                .request(http::Method::POST, infer_url)
+                .headers(headers);
+            let request = self.attach_body(request, body)?;
+            let endpoint = format!("POST {path}");
+            spin_telemetry::metrics::monotonic_counter!(
+                spin.llm_infer_count = 1,
+                model = model.as_str()
+            );
+            let resp = self.send(&endpoint, request).await?;
+            let resp = check_status("remote inference", resp).await?;
+
+            Ok(event_stream(resp.bytes_stream(), self.api_flavor, endpoint, permit).boxed())
+        }
+        .await;
+        result.map_err(|err| tag_request_id(err, &request_id))
+    }
+
+    #[instrument(name = "spin_llm_remote_http.infer_batch", skip(self, prompts), err(level = Level::INFO), fields(otel.kind = "client", prompt_count = prompts.len(), llm.endpoint = Empty))]
+    async fn infer_batch(
+        &mut self,
+        model: wasi_llm::InferencingModel,
+        prompts: Vec<String>,
+        params: wasi_llm::InferencingParams,
+    ) -> Result<Vec<wasi_llm::InferencingResult>, wasi_llm::Error> {
+        if self.batching && self.api_flavor == ApiFlavor::SpinCustom {
+            if let Some(results) = self.send_batch(&model, &prompts, &params).await? {
+                return Ok(results);
+            }
+            tracing::info!("Backend rejected batched inference; sending prompts individually");
+        }
+        let mut results = Vec::with_capacity(prompts.len());
+        for prompt in prompts {
+            results.push(self.infer(model.clone(), prompt, params).await?);
+        }
+        Ok(results)
+    }
+
+    #[instrument(name = "spin_llm_remote_http.generate_embeddings", skip(self, data), err(level = Level::INFO), fields(otel.kind = "client", llm.endpoint = Empty))]
+    async fn generate_embeddings(
+        &mut self,
+        model: wasi_llm::EmbeddingModel,
+        data: Vec<String>,
+    ) -> Result<wasi_llm::EmbeddingsResult, wasi_llm::Error> {
+        let Some(cache) = self.embedding_cache.clone() else {
+            return self.send_embeddings(&model, data).await;
+        };
+
+        let cached: Vec<_> = data.iter().map(|input| cache.get(&model, input)).collect();
+        let mut uncached: Vec<String> = Vec::new();
+        for (input, embedding) in data.iter().zip(&cached) {
+            if embedding.is_none() && !uncached.contains(input) {
+                uncached.push(input.clone());
+            }
+        }
+        let mut fresh = HashMap::new();
+        let mut usage = wasi_llm::EmbeddingsUsage {
+            prompt_token_count: 0,
+        };
+        if !uncached.is_empty() {
+            let result = self.send_embeddings(&model, uncached.clone()).await?;
+            if result.embeddings.len() != uncached.len() {
+                return Err(wasi_llm::Error::RuntimeError(format!(
+                    "Backend returned {} embeddings for {} inputs",
+                    result.embeddings.len(),
+                    uncached.len()
+                )));
+            }
+            for (input, embedding) in uncached.into_iter().zip(result.embeddings) {
+                cache.insert(&model, &input, embedding.clone());
+                fresh.insert(input, embedding);
+            }
+            usage = result.usage;
+        }
+
+        let embeddings: Vec<Vec<f32>> = data
+            .iter()
+            .zip(cached)
+            .filter_map(|(input, cached)| cached.or_else(|| fresh.get(input).cloned()))
+            .collect();
+        check_embedding_dimensions(&embeddings, self.expected_embedding_dim)?;
+        Ok(wasi_llm::EmbeddingsResult { embeddings, usage })
+    }
+}
+
+impl RemoteHttpLlmEngine {
+    /// Creates an engine for the backend at `url`. This may be a
+    /// `unix:///path/to/socket` URL for a backend listening on a Unix domain
+    /// socket, to which the engine's proxy and TLS settings don't apply.
+    pub fn new(url: Url, auth_token: String) -> Self {
+        let (url, unix_client) = unix::resolve(url);
+        RemoteHttpLlmEngine {
+            url,
+            auth_source: AuthSource::Static(auth_token),
+            fallback_url: None,
+            api_flavor: ApiFlavor::default(),
+            auth_scheme: AuthScheme::default(),
+            propagate_trace_context: true,
+            compress_requests: false,
+            infer_path: None,
+            embed_path: None,
+            chat_path: DEFAULT_CHAT_PATH.to_owned(),
+            models_path: None,
+            health_path: DEFAULT_HEALTH_PATH.to_owned(),
+            stop: Vec::new(),
+            seed: None,
+            model_defaults: HashMap::new(),
+            request_id: None,
+            presence_penalty: None,
+            frequency_penalty: None,
+            batching: false,
+            expected_embedding_dim: None,
+            normalize_embeddings: false,
+            embedding_cache: None,
+            concurrency_limit: None,
+            circuit_breaker: None,
+            timeout: None,
+            connect_timeout: None,
+            proxy: None,
+            user_agent: DEFAULT_USER_AGENT.to_owned(),
+            extra_root_certs: Vec::new(),
+            identity: None,
+            accept_invalid_certs: false,
+            max_retries: 0,
+            retry_backoff: DEFAULT_RETRY_BACKOFF,
+            client: Default::default(),
+            unix_client,
+        }
+    }
+
+    /// Creates an engine that sends its requests through `client`, for
+    /// example to share a connection pool with other subsystems. The client's
+    /// own timeout, proxy and TLS settings apply; configuring any of those on
+    /// the engine afterwards replaces `client` with one built by the engine.
+    pub fn with_client(url: Url, auth_token: String, client: Client) -> Self {
+        Self {
+            client: Arc::new(OnceCell::with_value(client)),
+            ..Self::new(url, auth_token)
+        }
+    }
+
+    /// Returns an engine that sends its requests to the backend at `url`, but
+    /// otherwise shares this engine's credentials, configuration, connection
+    /// pool and concurrency limit. This allows individual requests to be routed
+    /// to a different host without setting up a whole new engine.
+    pub fn with_endpoint(&self, url: Url) -> Self {
+        let (url, unix_client) = unix::resolve(url);
+        Self {
+            url,
+            unix_client,
+            ..self.clone()
+        }
+    }
+
+    /// Sets where the auth token comes from, replacing the token passed to
+    /// [`RemoteHttpLlmEngine::new`].
+    pub fn with_auth_source(mut self, auth_source: AuthSource) -> Self {
+        self.auth_source = auth_source;
+        self
+    }
+
+    /// Sets a secondary backend to which requests are sent if the primary
+    /// can't be reached or returns a 5xx response, after any retries. The
+    /// secondary must serve the same API and accept the same credentials.
+    pub fn with_fallback_endpoint(mut self, url: Url) -> Self {
+        self.fallback_url = Some(url);
+        self
+    }
+
+    /// Sets the API schema used to talk to the backend.
+    pub fn with_api_flavor(mut self, api_flavor: ApiFlavor) -> Self {
+        self.api_flavor = api_flavor;
+        self
+    }
+
+    /// Sets how the auth token is sent to the backend.
+    pub fn with_auth_scheme(mut self, auth_scheme: AuthScheme) -> Self {
+        self.auth_scheme = auth_scheme;
+        self
+    }
+
+    /// Sets whether requests carry the W3C `traceparent` and `tracestate`
+    /// headers for the current trace. This is enabled by default, but can be
+    /// turned off for backends that reject or complain about those headers.
+    pub fn with_trace_context_propagation(mut self, propagate_trace_context: bool) -> Self {
+        self.propagate_trace_context = propagate_trace_context;
+        self
+    }
+
+    /// Sets whether large request bodies, such as big batches of text to embed,
+    /// are gzip-compressed. This is off by default since not all backends
+    /// accept compressed requests. Compressed responses are always accepted.
+    pub fn with_request_compression(mut self, compress_requests: bool) -> Self {
+        self.compress_requests = compress_requests;
+        self
+    }
+
+    /// Sets the path to which inferencing requests are sent. By default this
+    /// is `/infer`, or `/v1/completions` for OpenAI-compatible backends.
+    ///
+    /// Paths are always appended to the base URL, whether or not they start
+    /// with `/`: with a base URL of `https://host/llm`, a path of `/infer`
+    /// sends requests to `https://host/llm/infer`.
+    pub fn with_infer_path(mut self, infer_path: impl Into<String>) -> Self {
+        self.infer_path = Some(infer_path.into());
+        self
+    }
+
+    /// Sets the path to which embeddings requests are sent. By default this is
+    /// `/embed`, or `/v1/embeddings` for OpenAI-compatible backends. The path
+    /// is resolved as described for [`RemoteHttpLlmEngine::with_infer_path`].
+    pub fn with_embed_path(mut self, embed_path: impl Into<String>) -> Self {
+        self.embed_path = Some(embed_path.into());
+        self
+    }
+
+    /// Sets the path to which chat requests are sent. The path is resolved as
+    /// described for [`RemoteHttpLlmEngine::with_infer_path`].
+    pub fn with_chat_path(mut self, chat_path: impl Into<String>) -> Self {
+        self.chat_path = chat_path.into();
+        self
+    }
+
+    /// Sets the path queried by [`RemoteHttpLlmEngine::list_models`]. By
+    /// default this is `/models`, or `/v1/models` for OpenAI-compatible
+    /// backends.
+    pub fn with_models_path(mut self, models_path: impl Into<String>) -> Self {
+        self.models_path = Some(models_path.into());
+        self
+    }
+
+    /// Sets sequences at which the backend should stop generating text. These
+    /// apply to every inferencing request, since the guest-facing inferencing
+    /// parameters have no way to carry them.
+    pub fn with_stop_sequences(
+        mut self,
+        stop: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        self.stop = stop.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Sets the seed used by the backend's sampler, so that repeated requests
+    /// with the same prompt and parameters produce the same output.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Sets the inferencing parameters used for `model` when a guest passes
+    /// none, so that each model can be tuned in one place. Parameters left
+    /// unset in `defaults` keep Spin's usual defaults.
+    pub fn with_model_defaults(
+        mut self,
+        model: impl Into<String>,
+        defaults: ModelDefaults,
+    ) -> Self {
+        self.model_defaults.insert(model.into(), defaults);
+        self
+    }
+
+    /// Sends `request_id` as the `x-request-id` header of every request,
+    /// instead of a random ID per request, so that a host can correlate
+    /// backend requests with its own. Either way, errors from a request
+    /// include its ID.
+    pub fn with_request_id(mut self, request_id: impl Into<String>) -> Self {
+        self.request_id = Some(request_id.into());
+        self
+    }
+
+    /// Sets the OpenAI-style presence penalty, which discourages tokens that
+    /// have appeared at all in the text so far. It is sent with every request
+    /// in addition to the guest's `repeat-penalty`: the Spin flavor passes it
+    /// through as the `presencePenalty` option, and the OpenAI flavor, which
+    /// has no repeat penalty, as `presence_penalty`.
+    pub fn with_presence_penalty(mut self, penalty: f32) -> Self {
+        self.presence_penalty = Some(penalty);
+        self
+    }
+
+    /// Sets the OpenAI-style frequency penalty, which discourages tokens in
+    /// proportion to how often they have appeared so far. Like
+    /// [`RemoteHttpLlmEngine::with_presence_penalty`], it is sent as the
+    /// `frequencyPenalty` option by the Spin flavor and as `frequency_penalty`
+    /// by the OpenAI flavor.
+    pub fn with_frequency_penalty(mut self, penalty: f32) -> Self {
+        self.frequency_penalty = Some(penalty);
+        self
+    }
+
+    /// Sets the path queried by [`RemoteHttpLlmEngine::health`].
+    pub fn with_health_path(mut self, health_path: impl Into<String>) -> Self {
+        self.health_path = health_path.into();
+        self
+    }
+
+    /// Sets the total time allowed for each request to the backend, from
+    /// connecting through to reading the whole response. By default requests
+    /// never time out.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self.client = Default::default();
+        self
+    }
+
+    /// Sends the prompts given to `infer_batch` in a single request, as a
+    /// `prompts` array answered by an array of results. If the backend rejects
+    /// the batch with a client error, the prompts are sent one at a time
+    /// instead. Batching is not supported for OpenAI-compatible backends,
+    /// which don't report token usage per prompt.
+    pub fn with_batching(mut self, batching: bool) -> Self {
+        self.batching = batching;
+        self
+    }
+
+    /// Rejects embeddings responses containing vectors whose length is not
+    /// `dim`. Regardless of this setting, responses are rejected if their
+    /// vectors are empty or differ in length from one another.
+    pub fn with_expected_embedding_dim(mut self, dim: usize) -> Self {
+        self.expected_embedding_dim = Some(dim);
+        self
+    }
+
+    /// Sets whether embeddings are scaled to unit length (L2 norm) before being
+    /// returned, for vector stores that expect normalized vectors. Vectors of
+    /// all zeros are returned unchanged.
+    pub fn with_embedding_normalization(mut self, normalize_embeddings: bool) -> Self {
+        self.normalize_embeddings = normalize_embeddings;
+        self
+    }
+
+    /// Allows at most `max_concurrent_requests` requests to the backend to be
+    /// in flight at once, across this engine and its clones. Further requests
+    /// wait for an earlier one to complete. By default there is no limit. A
+    /// limit of zero is treated as one, so that requests are still made.
+    pub fn with_concurrency_limit(mut self, max_concurrent_requests: usize) -> Self {
+        self.concurrency_limit = Some(Arc::new(Semaphore::new(max_concurrent_requests.max(1))));
+        self
+    }
+
+    /// Sets the time allowed to establish a connection to the backend. Unlike
+    /// [`RemoteHttpLlmEngine::with_timeout`], this doesn't limit how long a
+    /// connected backend may take to respond, so an unreachable backend can
+    /// be detected quickly while slow generations are still tolerated.
+    pub fn with_connect_timeout(mut self, connect_timeout: Duration) -> Self {
+        self.connect_timeout = Some(connect_timeout);
+        self.client = Default::default();
+        self
+    }
+
+    /// Sends all requests through the proxy at `proxy`. Without this, the
+    /// `HTTP_PROXY` and `HTTPS_PROXY` environment variables are respected.
+    pub fn with_proxy(mut self, proxy: impl Into<String>) -> Self {
+        self.proxy = Some(proxy.into());
+        self.client = Default::default();
+        self
+    }
+
+    /// Sets the `User-Agent` header sent with every request, so that backend
+    /// operators can tell which application traffic comes from.
+    pub fn with_user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = user_agent.into();
+        self.client = Default::default();
+        self
+    }
+
+    /// Trusts `cert` as a root certificate authority for the backend's TLS
+    /// certificate, in addition to the system's roots.
+    pub fn with_root_certificate(mut self, cert: Certificate) -> Self {
+        self.extra_root_certs.push(cert);
+        self.client = Default::default();
+        self
+    }
+
+    /// Presents `identity` as a client certificate to backends that require
+    /// mutual TLS.
+    pub fn with_identity(mut self, identity: Identity) -> Self {
+        self.identity = Some(identity);
+        self.client = Default::default();
+        self
+    }
+
+    /// Disables validation of the backend's TLS certificate, so that any
+    /// certificate (including an expired or self-signed one, or one for a
+    /// different host) is accepted. This leaves requests, including the auth
+    /// token they carry, open to interception and should only be used for
+    /// local testing.
+    pub fn danger_accept_invalid_certs(mut self) -> Self {
+        self.accept_invalid_certs = true;
+        self.client = Default::default();
+        self
+    }
+
+    /// Stops sending requests to the backend for `cooldown` once
+    /// `failure_threshold` consecutive requests have failed to connect, timed
+    /// out or received a 5xx response, failing them immediately instead. After
+    /// the cooldown a single request is sent to test whether the backend has
+    /// recovered. The failure count is shared by this engine and its clones.
+    pub fn with_circuit_breaker(mut self, failure_threshold: u32, cooldown: Duration) -> Self {
+        self.circuit_breaker = Some(Arc::new(CircuitBreaker::new(failure_threshold, cooldown)));
+        self
+    }
+
+    /// Keeps the embeddings of up to `capacity` recently embedded inputs per
+    /// engine and its clones, so that repeated inputs aren't sent to the
+    /// backend again. Only uncached inputs are sent, and the reported usage
+    /// covers just those.
+    pub fn with_embedding_cache(mut self, capacity: usize) -> Self {
+        self.embedding_cache = Some(Arc::new(EmbeddingCache::new(capacity)));
+        self
+    }
+
+    /// Retries requests that fail to connect or that receive a 5xx or 429
+    /// response, up to `max_retries` times after the first attempt. The delay
+    /// before each retry doubles from `backoff`, with random jitter, unless
+    /// the response gives a `Retry-After` delay. Other client errors (4xx) are
+    /// never retried.
+    pub fn with_retries(mut self, max_retries: u32, backoff: Duration) -> Self {
+        self.max_retries = max_retries;
+        self.retry_backoff = backoff;
+        self
+    }
+
+    /// Performs an inference, returning the backend's whole response body as
+    /// well as the fields Spin understands, so that vendor-specific fields such
+    /// as log probabilities can be used.
+    #[instrument(name = "spin_llm_remote_http.infer", skip(self, prompt), err(level = Level::INFO), fields(otel.kind = "client", llm.endpoint = Empty))]
+    pub async fn infer_raw(
+        &mut self,
+        model: wasi_llm::InferencingModel,
+        prompt: String,
+        params: wasi_llm::InferencingParams,
+    ) -> Result<RawInferencingResult, wasi_llm::Error> {
+        let request_id = self.request_id();
+        let result: Result<_, wasi_llm::Error> = async {
+            let headers = self.request_headers(&request_id)?;
+            let client = self.client()?;
+
+            let body = self.infer_request_body(&model, &prompt, &params, false)?;
+            let _permit = self.acquire_permit().await?;
+            let path = self.infer_path();
+            let infer_url = self.endpoint_url(path)?;
+            tracing::info!("Sending remote inference request to {infer_url}");
+
+            let request = client
+                .request(http::Method::POST, infer_url)
+                .headers(headers);
+            let request = self.attach_body(request, body)?;
+            let endpoint = format!("POST {path}");
+            let start = Instant::now();
+            let resp = self.send(&endpoint, request).await;
+            spin_telemetry::metrics::histogram!(
+                spin.llm_infer_duration_ms = start.elapsed().as_secs_f64() * 1000.0,
+                model = model.as_str()
+            );
+            spin_telemetry::metrics::monotonic_counter!(
+                spin.llm_infer_count = 1,
+                model = model.as_str()
+            );
+            let resp = resp?;
+            let resp = check_status("remote inference", resp).await?;
+
+            let body = match resp.json::<serde_json::Value>().await {
+                Ok(body) => body,
+                Err(err) if err.is_timeout() => return Err(request_error(&endpoint, err)),
+                Err(err) => {
+                    return Err(wasi_llm::Error::RuntimeError(format!(
+                        "Failed to deserialize response for \"{endpoint}\": {err}"
+                    )))
+                }
+            };
+            let result = parse_infer_response(self.api_flavor, &body, &endpoint)?;
+            record_inference_usage(&model, &result.result.usage);
+            Ok(RawInferencingResult { result, body })
+        }
+        .await;
+        result.map_err(|err| tag_request_id(err, &request_id))
+    }
+
+    /// Sends a one-token inference for `model`, so that the backend loads it
+    /// before the first real request needs it. Hosts can call this during
+    /// startup to keep model loading time out of user-facing latency.
+    pub async fn warmup(&mut self, model: &str) -> Result<(), wasi_llm::Error> {
+        let params = wasi_llm::InferencingParams {
+            max_tokens: 1,
+            ..self.default_params(model)
+        };
+        let start = Instant::now();
+        self.infer_raw(model.to_owned(), "Hello".to_owned(), params)
+            .await?;
+        tracing::info!("Warmed up model {model} in {:?}", start.elapsed());
+        Ok(())
+    }
+
+    /// Generates the next message in a conversation, leaving the backend to
+    /// apply the model's chat template to `messages`. Requests use the OpenAI
+    /// chat completions API whatever the engine's [`ApiFlavor`], and the
+    /// `top_k` and repeat penalty parameters are not sent.
+    #[instrument(name = "spin_llm_remote_http.infer_chat", skip(self, messages), err(level = Level::INFO), fields(otel.kind = "client", llm.endpoint = Empty))]
+    pub async fn infer_chat(
+        &mut self,
+        model: wasi_llm::InferencingModel,
+        messages: Vec<ChatMessage>,
+        params: wasi_llm::InferencingParams,
+    ) -> Result<DetailedInferencingResult, wasi_llm::Error> {
+        let request_id = self.request_id();
+        let result: Result<_, wasi_llm::Error> = async {
+            let headers = self.request_headers(&request_id)?;
+            let client = self.client()?;
 
-        let inference_options = InferRequestBodyParams {
+            let body = serde_json::to_string(&openai::ChatCompletionRequest {
+                model: &model,
+                messages: &messages,
+                max_tokens: params.max_tokens,
+                temperature: params.temperature,
+                top_p: params.top_p,
+                stop: &self.stop,
+                seed: self.seed,
+                presence_penalty: self.presence_penalty,
+                frequency_penalty: self.frequency_penalty,
+            })
+            .map_err(|_| wasi_llm::Error::RuntimeError("Failed to serialize JSON".to_string()))?;
+            let _permit = self.acquire_permit().await?;
+            let path = &self.chat_path;
+            let chat_url = self.endpoint_url(path)?;
+            tracing::info!("Sending remote chat inference request to {chat_url}");
+
+            let request = client
+                .request(http::Method::POST, chat_url)
+                .headers(headers);
+            let request = self.attach_body(request, body)?;
+            let endpoint = format!("POST {path}");
+            let start = Instant::now();
+            let resp = self.send(&endpoint, request).await;
+            spin_telemetry::metrics::histogram!(
+                spin.llm_infer_duration_ms = start.elapsed().as_secs_f64() * 1000.0,
+                model = model.as_str()
+            );
+            spin_telemetry::metrics::monotonic_counter!(
+                spin.llm_infer_count = 1,
+                model = model.as_str()
+            );
+            let resp = check_status("remote chat inference", resp?).await?;
+
+            let detailed = match resp.json::<openai::ChatCompletionResponse>().await {
+                Ok(val) => val.into_result(),
+                Err(err) if err.is_timeout() => Err(request_error(&endpoint, err)),
+                Err(err) => Err(wasi_llm::Error::RuntimeError(format!(
+                    "Failed to deserialize response for \"{endpoint}\": {err}"
+                ))),
+            }?;
+            record_inference_usage(&model, &detailed.result.usage);
+            Ok(detailed)
+        }
+        .await;
+        result.map_err(|err| tag_request_id(err, &request_id))
+    }
+
+    /// Lists the models the backend serves.
+    #[instrument(name = "spin_llm_remote_http.list_models", skip(self), err(level = Level::INFO), fields(otel.kind = "client"))]
+    pub async fn list_models(&self) -> Result<Vec<ModelInfo>, wasi_llm::Error> {
+        let headers = self.request_headers(&self.request_id())?;
+        let client = self.client()?;
+
+        let path = self
+            .models_path
+            .as_deref()
+            .unwrap_or_else(|| self.api_flavor.models_path());
+        let models_url = self.endpoint_url(path)?;
+        let request = client
+            .request(http::Method::GET, models_url)
+            .headers(headers);
+        let endpoint = format!("GET {path}");
+        let resp = self.send(&endpoint, request).await?;
+        let resp = check_status("remote model listing", resp).await?;
+
+        match resp.json::<ModelsResponseBody>().await {
+            Ok(ModelsResponseBody::List(models) | ModelsResponseBody::Wrapped { data: models }) => {
+                Ok(models)
+            }
+            Err(err) if err.is_timeout() => Err(request_error(&endpoint, err)),
+            Err(err) => Err(wasi_llm::Error::RuntimeError(format!(
+                "Failed to deserialize response for \"{endpoint}\": {err}"
+            ))),
+        }
+    }
+
+    /// Checks that the backend is reachable and reports itself healthy, by
+    /// sending a GET request to the health path and expecting a successful
+    /// status.
+    #[instrument(name = "spin_llm_remote_http.health", skip(self), err(level = Level::INFO), fields(otel.kind = "client"))]
+    pub async fn health(&self) -> Result<(), wasi_llm::Error> {
+        let headers = self.request_headers(&self.request_id())?;
+        let client = self.client()?;
+
+        let path = &self.health_path;
+        let health_url = self.endpoint_url(path)?;
+        let request = client
+            .request(http::Method::GET, health_url)
+            .headers(headers);
+        let endpoint = format!("GET {path}");
+        let resp = self.send(&endpoint, request).await.map_err(|err| {
+            wasi_llm::Error::RuntimeError(format!(
+                "LLM backend unreachable at {}: {}",
+                self.url,
+                error_detail(err)
+            ))
+        })?;
+        check_status("remote health check", resp).await?;
+        Ok(())
+    }
+
+    /// Embeds `data` with a request to the backend, bypassing any cache.
+    async fn send_embeddings(
+        &self,
+        model: &str,
+        data: Vec<String>,
+    ) -> Result<wasi_llm::EmbeddingsResult, wasi_llm::Error> {
+        let request_id = self.request_id();
+        let result: Result<_, wasi_llm::Error> = async {
+            let headers = self.request_headers(&request_id)?;
+            let client = self.client()?;
+
+            let body = match self.api_flavor {
+                ApiFlavor::SpinCustom => serde_json::to_string(&json!({
+                    "model": model,
+                    "input": data
+                })),
+                ApiFlavor::OpenAi => serde_json::to_string(&openai::EmbeddingRequest {
+                    model,
+                    input: &data,
+                }),
+            }
+            .map_err(|_| wasi_llm::Error::RuntimeError("Failed to serialize JSON".to_string()))?;
+            let _permit = self.acquire_permit().await?;
+
+            let path = self.embed_path();
+            let request = client
+                .request(http::Method::POST, self.endpoint_url(path)?)
+                .headers(headers);
+            let request = self.attach_body(request, body)?;
+            let endpoint = format!("POST {path}");
+            let start = Instant::now();
+            let resp = self.send(&endpoint, request).await;
+            spin_telemetry::metrics::histogram!(
+                spin.llm_embeddings_duration_ms = start.elapsed().as_secs_f64() * 1000.0,
+                model = model
+            );
+            spin_telemetry::metrics::monotonic_counter!(
+                spin.llm_embeddings_count = 1,
+                model = model
+            );
+            let resp = resp?;
+            let resp = check_status("remote embeddings generation", resp).await?;
+
+            let mut result = match self.api_flavor {
+                ApiFlavor::SpinCustom => match resp.json::<EmbeddingResponseBody>().await {
+                    Ok(val) => Ok(wasi_llm::EmbeddingsResult {
+                        embeddings: val.embeddings,
+                        usage: wasi_llm::EmbeddingsUsage {
+                            prompt_token_count: val.usage.prompt_token_count,
+                        },
+                    }),
+                    Err(err) if err.is_timeout() => Err(request_error(&endpoint, err)),
+                    Err(err) => Err(wasi_llm::Error::RuntimeError(format!(
+                        "Failed to deserialize response for \"{endpoint}\": {err}"
+                    ))),
+                },
+                ApiFlavor::OpenAi => match resp.json::<openai::EmbeddingResponse>().await {
+                    Ok(val) => Ok(val.into_result()),
+                    Err(err) if err.is_timeout() => Err(request_error(&endpoint, err)),
+                    Err(err) => Err(wasi_llm::Error::RuntimeError(format!(
+                        "Failed to deserialize response for \"{endpoint}\": {err}"
+                    ))),
+                },
+            }?;
+            check_embedding_dimensions(&result.embeddings, self.expected_embedding_dim)?;
+            if self.normalize_embeddings {
+                result.embeddings.iter_mut().for_each(|e| normalize(e));
+            }
+            spin_telemetry::metrics::monotonic_counter!(
+                spin.llm_embedding_tokens = u64::from(result.usage.prompt_token_count),
+                model = model
+            );
+            Ok(result)
+        }
+        .await;
+        result.map_err(|err| tag_request_id(err, &request_id))
+    }
+
+    fn infer_path(&self) -> &str {
+        self.infer_path
+            .as_deref()
+            .unwrap_or_else(|| self.api_flavor.infer_path())
+    }
+
+    fn embed_path(&self) -> &str {
+        self.embed_path
+            .as_deref()
+            .unwrap_or_else(|| self.api_flavor.embed_path())
+    }
+
+    fn endpoint_url(&self, path: &str) -> Result<Url, wasi_llm::Error> {
+        join_endpoint(&self.url, path)
+    }
+
+    fn client(&self) -> Result<Client, wasi_llm::Error> {
+        self.client
+            .get_or_try_init(|| {
+                let mut builder = Client::builder().user_agent(&self.user_agent);
+                if let Some(timeout) = self.timeout {
+                    builder = builder.timeout(timeout);
+                }
+                if let Some(connect_timeout) = self.connect_timeout {
+                    builder = builder.connect_timeout(connect_timeout);
+                }
+                if let Some(proxy) = &self.proxy {
+                    let proxy = reqwest::Proxy::all(proxy).map_err(|err| {
+                        wasi_llm::Error::RuntimeError(format!("Invalid proxy '{proxy}': {err}"))
+                    })?;
+                    builder = builder.proxy(proxy);
+                }
+                for cert in &self.extra_root_certs {
+                    builder = builder.add_root_certificate(cert.clone());
+                }
+                if let Some(identity) = &self.identity {
+                    builder = builder.identity(identity.clone());
+                }
+                if self.accept_invalid_certs {
+                    tracing::warn!("TLS certificate validation is disabled for {}", self.url);
+                    builder = builder.danger_accept_invalid_certs(true);
+                }
+                builder.build().map_err(|err| {
+                    wasi_llm::Error::RuntimeError(format!("Failed to create HTTP client: {err}"))
+                })
+            })
+            .cloned()
+    }
+
+    fn infer_request_body(
+        &self,
+        model: &str,
+        prompt: &str,
+        params: &wasi_llm::InferencingParams,
+        stream: bool,
+    ) -> Result<String, wasi_llm::Error> {
+        match self.api_flavor {
+            ApiFlavor::SpinCustom => {
+                let mut body = json!({
+                    "model": model,
+                    "prompt": prompt,
+                    "options": self.infer_options(params)
+                });
+                if stream {
+                    body["stream"] = json!(true);
+                }
+                serde_json::to_string(&body)
+            }
+            ApiFlavor::OpenAi => serde_json::to_string(&openai::CompletionRequest {
+                model,
+                prompt,
+                max_tokens: params.max_tokens,
+                temperature: params.temperature,
+                top_p: params.top_p,
+                stop: &self.stop,
+                seed: self.seed,
+                presence_penalty: self.presence_penalty,
+                frequency_penalty: self.frequency_penalty,
+                stream,
+            }),
+        }
+        .map_err(|_| wasi_llm::Error::RuntimeError("Failed to serialize JSON".to_string()))
+    }
+
+    fn infer_options(&self, params: &wasi_llm::InferencingParams) -> InferRequestBodyParams {
+        InferRequestBodyParams {
             max_tokens: params.max_tokens,
             repeat_penalty: params.repeat_penalty,
             repeat_penalty_last_n_token_count: params.repeat_penalty_last_n_token_count,
             temperature: params.temperature,
             top_k: params.top_k,
             top_p: params.top_p,
-        };
-        let body = serde_json::to_string(&json!({
-            "model": model,
-            "prompt": prompt,
-            "options": inference_options
-        }))
-        .map_err(|_| wasi_llm::Error::RuntimeError("Failed to serialize JSON".to_string()))?;
-
-        let infer_url = self
-            .url
-            .join("/infer")
-            .map_err(|_| wasi_llm::Error::RuntimeError("Failed to create URL".to_string()))?;
-        tracing::info!("Sending remote inference request to {infer_url}");
-
-        let resp = client
-            .request(http::Method::POST, infer_url)
-            .headers(headers)
-            .body(body)
-            .send()
-            .await
-            .map_err(|err| {
-                wasi_llm::Error::RuntimeError(format!("POST /infer request error: {err}"))
-            })?;
+            stop: self.stop.clone(),
+            seed: self.seed,
+            presence_penalty: self.presence_penalty,
+            frequency_penalty: self.frequency_penalty,
+        }
+    }
 
-        match resp.json::<InferResponseBody>().await {
-            Ok(val) => Ok(wasi_llm::InferencingResult {
-                text: val.text,
-                usage: wasi_llm::InferencingUsage {
-                    prompt_token_count: val.usage.prompt_token_count,
-                    generated_token_count: val.usage.generated_token_count,
-                },
-            }),
-            Err(err) => Err(wasi_llm::Error::RuntimeError(format!(
-                "Failed to deserialize response for \"POST  /index\": {err}"
-            ))),
+    /// Sets the body of a request, compressing it if request compression is
+    /// enabled and the body is large enough.
+    fn attach_body(
+        &self,
+        request: RequestBuilder,
+        body: String,
+    ) -> Result<RequestBuilder, wasi_llm::Error> {
+        if !self.compress_requests || body.len() < COMPRESSION_THRESHOLD {
+            return Ok(request.body(body));
         }
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        let compressed = encoder
+            .write_all(body.as_bytes())
+            .and_then(|_| encoder.finish())
+            .map_err(|err| {
+                wasi_llm::Error::RuntimeError(format!("Failed to compress request body: {err}"))
+            })?;
+        Ok(request.header("content-encoding", "gzip").body(compressed))
     }
 
-    #[instrument(name = "spin_llm_remote_http.generate_embeddings", skip(self, data), err(level = Level::INFO), fields(otel.kind = "client"))]
-    async fn generate_embeddings(
-        &mut self,
-        model: wasi_llm::EmbeddingModel,
-        data: Vec<String>,
-    ) -> Result<wasi_llm::EmbeddingsResult, wasi_llm::Error> {
-        let client = self.client.get_or_insert_with(Default::default);
+    /// The ID to send with the next request: the one set with
+    /// [`RemoteHttpLlmEngine::with_request_id`], or else a new random one.
+    fn request_id(&self) -> String {
+        self.request_id.clone().unwrap_or_else(new_request_id)
+    }
 
+    fn request_headers(&self, request_id: &str) -> Result<HeaderMap, wasi_llm::Error> {
         let mut headers = HeaderMap::new();
-        headers.insert(
-            "authorization",
-            HeaderValue::from_str(&format!("bearer {}", self.auth_token)).map_err(|_| {
-                wasi_llm::Error::RuntimeError("Failed to create authorization header".to_string())
-            })?,
-        );
-        spin_telemetry::inject_trace_context(&mut headers);
-
-        let body = serde_json::to_string(&json!({
-            "model": model,
-            "input": data
-        }))
-        .map_err(|_| wasi_llm::Error::RuntimeError("Failed to serialize JSON".to_string()))?;
-
-        let resp = client
-            .request(
-                http::Method::POST,
-                self.url.join("/embed").map_err(|_| {
-                    wasi_llm::Error::RuntimeError("Failed to create URL".to_string())
+        self.apply_auth(&mut headers)?;
+        let request_id = HeaderValue::from_str(request_id).map_err(|_| {
+            wasi_llm::Error::RuntimeError(format!("Invalid request ID '{request_id}'"))
+        })?;
+        headers.insert(REQUEST_ID_HEADER, request_id);
+        if self.propagate_trace_context {
+            spin_telemetry::inject_trace_context(&mut headers);
+        }
+        Ok(headers)
+    }
+
+    fn apply_auth(&self, headers: &mut HeaderMap) -> Result<(), wasi_llm::Error> {
+        if self.auth_scheme == AuthScheme::None {
+            return Ok(());
+        }
+        let auth_token = self.auth_source.resolve()?;
+        let (name, value) = match &self.auth_scheme {
+            AuthScheme::Bearer => (
+                HeaderName::from_static("authorization"),
+                format!("bearer {auth_token}"),
+            ),
+            AuthScheme::ApiKeyHeader(name) => (
+                HeaderName::try_from(name.as_str()).map_err(|_| {
+                    wasi_llm::Error::RuntimeError(format!("Invalid auth header name '{name}'"))
                 })?,
-            )
-            .headers(headers)
-            .body(body)
-            .send()
+                auth_token,
+            ),
+            AuthScheme::Basic => (
+                HeaderName::from_static("authorization"),
+                format!("Basic {}", BASE64_STANDARD.encode(&auth_token)),
+            ),
+            AuthScheme::None => return Ok(()),
+        };
+        let mut value = HeaderValue::from_str(&value).map_err(|_| {
+            wasi_llm::Error::RuntimeError("Failed to create authorization header".to_string())
+        })?;
+        value.set_sensitive(true);
+        headers.insert(name, value);
+        Ok(())
+    }
+}
+
+impl RemoteHttpLlmEngine {
+    /// Sends a batched inference request, returning `None` if the backend
+    /// rejected it with a client error.
+    async fn send_batch(
+        &self,
+        model: &str,
+        prompts: &[String],
+        params: &wasi_llm::InferencingParams,
+    ) -> Result<Option<Vec<wasi_llm::InferencingResult>>, wasi_llm::Error> {
+        let request_id = self.request_id();
+        let result: Result<_, wasi_llm::Error> = async {
+            let headers = self.request_headers(&request_id)?;
+            let client = self.client()?;
+
+            let body = serde_json::to_string(&json!({
+                "model": model,
+                "prompts": prompts,
+                "options": self.infer_options(params)
+            }))
+            .map_err(|_| wasi_llm::Error::RuntimeError("Failed to serialize JSON".to_string()))?;
+            let _permit = self.acquire_permit().await?;
+
+            let path = self.infer_path();
+            let infer_url = self.endpoint_url(path)?;
+            tracing::info!("Sending remote batched inference request to {infer_url}");
+
+            let request = client
+                .request(http::Method::POST, infer_url)
+                .headers(headers);
+            let request = self.attach_body(request, body)?;
+            let endpoint = format!("POST {path}");
+            spin_telemetry::metrics::monotonic_counter!(
+                spin.llm_infer_count = prompts.len() as u64,
+                model = model
+            );
+            let resp = self.send(&endpoint, request).await?;
+            if resp.status().is_client_error() && resp.status() != StatusCode::TOO_MANY_REQUESTS {
+                return Ok(None);
+            }
+            let resp = check_status("remote batched inference", resp).await?;
+
+            let results = match resp.json::<Vec<InferResponseBody>>().await {
+                Ok(val) => val,
+                Err(err) if err.is_timeout() => return Err(request_error(&endpoint, err)),
+                Err(err) => {
+                    return Err(wasi_llm::Error::RuntimeError(format!(
+                        "Failed to deserialize response for \"{endpoint}\": {err}"
+                    )))
+                }
+            };
+            if results.len() != prompts.len() {
+                return Err(wasi_llm::Error::RuntimeError(format!(
+                    "{endpoint} returned {} results for {} prompts",
+                    results.len(),
+                    prompts.len()
+                )));
+            }
+            let results: Vec<wasi_llm::InferencingResult> =
+                results.into_iter().map(Into::into).collect();
+            for result in &results {
+                record_inference_usage(model, &result.usage);
+            }
+            Ok(Some(results))
+        }
+        .await;
+        result.map_err(|err| tag_request_id(err, &request_id))
+    }
+
+    /// Waits for a slot under the concurrency limit, if one is configured.
+    async fn acquire_permit(&self) -> Result<Option<OwnedSemaphorePermit>, wasi_llm::Error> {
+        let Some(semaphore) = &self.concurrency_limit else {
+            return Ok(None);
+        };
+        semaphore
+            .clone()
+            .acquire_owned()
             .await
+            .map(Some)
             .map_err(|err| {
-                wasi_llm::Error::RuntimeError(format!("POST /embed request error: {err}"))
+                wasi_llm::Error::RuntimeError(format!("Failed to acquire request permit: {err}"))
+            })
+    }
+
+    /// Sends a request to the primary backend, failing over to the fallback
+    /// backend if there is one and the primary can't be reached or returns a
+    /// 5xx response. The URL of the backend that served the request is
+    /// recorded on the current span as `llm.endpoint`.
+    async fn send(
+        &self,
+        endpoint: &str,
+        request: RequestBuilder,
+    ) -> Result<Response, wasi_llm::Error> {
+        let fallback = self
+            .fallback_url
+            .as_ref()
+            .and_then(|url| Some((url, request.try_clone()?)));
+        let result = self.send_guarded(endpoint, request).await;
+        let failed = match &result {
+            Ok(resp) => resp.status().is_server_error(),
+            Err(_) => true,
+        };
+        let Some((fallback_url, fallback)) = fallback.filter(|_| failed) else {
+            if result.is_ok() {
+                tracing::Span::current().record("llm.endpoint", self.url.as_str());
+            }
+            return result;
+        };
+
+        let (client, fallback) = fallback.build_split();
+        let mut fallback = fallback.map_err(|err| request_error(endpoint, err))?;
+        let Some(url) = rebase_url(fallback.url(), &self.url, fallback_url) else {
+            return result;
+        };
+        *fallback.url_mut() = url;
+        tracing::warn!("{endpoint} failed on the primary backend; trying {fallback_url}");
+        let result = self
+            .send_with_retries(endpoint, RequestBuilder::from_parts(client, fallback), None)
+            .await;
+        if result.is_ok() {
+            tracing::Span::current().record("llm.endpoint", fallback_url.as_str());
+        }
+        result
+    }
+
+    /// Sends a request, unless the circuit breaker is open, and records
+    /// whether it succeeded.
+    async fn send_guarded(
+        &self,
+        endpoint: &str,
+        request: RequestBuilder,
+    ) -> Result<Response, wasi_llm::Error> {
+        let Some(breaker) = &self.circuit_breaker else {
+            return self
+                .send_with_retries(endpoint, request, self.unix_client.as_ref())
+                .await;
+        };
+        if !breaker.allow_request() {
+            return Err(wasi_llm::Error::RuntimeError(
+                "llm backend circuit open".to_string(),
+            ));
+        }
+        let result = self
+            .send_with_retries(endpoint, request, self.unix_client.as_ref())
+            .await;
+        match &result {
+            Ok(resp) if !resp.status().is_server_error() => breaker.record_success(),
+            _ => breaker.record_failure(),
+        }
+        result
+    }
+
+    /// Sends a request, over `socket` if given, retrying transient failures
+    /// as configured by [`RemoteHttpLlmEngine::with_retries`].
+    async fn send_with_retries(
+        &self,
+        endpoint: &str,
+        request: RequestBuilder,
+        socket: Option<&unix::UnixClient>,
+    ) -> Result<Response, wasi_llm::Error> {
+        let mut attempts = 0;
+        loop {
+            attempts += 1;
+            let attempt = request.try_clone().ok_or_else(|| {
+                wasi_llm::Error::RuntimeError(format!("{endpoint} request could not be cloned"))
             })?;
+            let result = self.execute(endpoint, attempt, socket).await;
+            let retryable = match &result {
+                Ok(resp) => {
+                    resp.status().is_server_error()
+                        || resp.status() == StatusCode::TOO_MANY_REQUESTS
+                }
+                Err(err) => err.retryable,
+            };
+            if !retryable || attempts > self.max_retries {
+                return match result {
+                    Ok(resp) if retryable && attempts > 1 && resp.status().is_server_error() => {
+                        Err(wasi_llm::Error::RuntimeError(format!(
+                            "{endpoint} failed after {attempts} attempts: server returned {}",
+                            resp.status()
+                        )))
+                    }
+                    Ok(resp) => Ok(resp),
+                    Err(err) => {
+                        let mut error = err.error;
+                        if let wasi_llm::Error::RuntimeError(message) = &mut error {
+                            if attempts > 1 {
+                                message.push_str(&format!(" (after {attempts} attempts)"));
+                            }
+                        }
+                        Err(error)
+                    }
+                };
+            }
+            // Honour the backend's request to wait, within reason.
+            let delay = match result.as_ref().ok().and_then(retry_after) {
+                Some(delay) => delay.min(MAX_RETRY_BACKOFF),
+                None => retry_delay(self.retry_backoff, attempts),
+            };
+            tracing::debug!("Retrying {endpoint} in {delay:?} (attempt {attempts} failed)");
+            tokio::time::sleep(delay).await;
+        }
+    }
 
-        match resp.json::<EmbeddingResponseBody>().await {
-            Ok(val) => Ok(wasi_llm::EmbeddingsResult {
-                embeddings: val.embeddings,
-                usage: wasi_llm::EmbeddingsUsage {
-                    prompt_token_count: val.usage.prompt_token_count,
-                },
-            }),
-            Err(err) => Err(wasi_llm::Error::RuntimeError(format!(
-                "Failed to deserialize response  for \"POST  /embed\": {err}"
-            ))),
+    /// Sends a single request, over `socket` if given or else with the
+    /// request's own client.
+    async fn execute(
+        &self,
+        endpoint: &str,
+        request: RequestBuilder,
+        socket: Option<&unix::UnixClient>,
+    ) -> Result<Response, TransportError> {
+        let Some(socket) = socket else {
+            return request.send().await.map_err(|err| TransportError {
+                retryable: err.is_connect() || err.is_timeout() || err.is_request(),
+                error: request_error(endpoint, err),
+            });
+        };
+        let request = request
+            .header(USER_AGENT, &self.user_agent)
+            .build()
+            .map_err(|err| TransportError {
+                retryable: false,
+                error: request_error(endpoint, err),
+            })?;
+        let response = socket.execute(request);
+        let response = match self.timeout {
+            Some(timeout) => {
+                tokio::time::timeout(timeout, response)
+                    .await
+                    .map_err(|_| TransportError {
+                        retryable: true,
+                        error: wasi_llm::Error::RuntimeError(format!(
+                            "{endpoint} request timed out"
+                        )),
+                    })?
+            }
+            None => response.await,
+        };
+        response.map_err(|err| TransportError {
+            retryable: err.is_connect() || err.is_closed() || err.is_timeout(),
+            error: wasi_llm::Error::RuntimeError(format!("{endpoint} request error: {err}")),
+        })
+    }
+}
+
+/// A failure to get any response from the backend.
+struct TransportError {
+    error: wasi_llm::Error,
+    /// Whether the failure may be transient, such as a refused connection.
+    retryable: bool,
+}
+
+/// The delay before the retry following the given (1-based) attempt: the base
+/// delay doubled for each previous attempt, jittered into the upper half of
+/// that window so that many clients don't retry in lockstep.
+fn retry_delay(base: Duration, attempt: u32) -> Duration {
+    let window = base
+        .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+        .min(MAX_RETRY_BACKOFF);
+    window.mul_f64(rand::thread_rng().gen_range(0.5..=1.0))
+}
+
+/// Resolves `path` beneath `base`. Joining an absolute path onto the base URL
+/// would discard its path, and joining a relative one would discard its last
+/// segment unless it ends with `/`, which would break backends served under a
+/// path prefix by a reverse proxy.
+fn join_endpoint(base: &Url, path: &str) -> Result<Url, wasi_llm::Error> {
+    let mut base = base.clone();
+    if !base.path().ends_with('/') {
+        let base_path = format!("{}/", base.path());
+        base.set_path(&base_path);
+    }
+    base.join(path.trim_start_matches('/'))
+        .map_err(|_| wasi_llm::Error::RuntimeError("Failed to create URL".to_string()))
+}
+
+/// Moves `url`, which lies beneath the base URL `from`, to the same place
+/// beneath `to`.
+fn rebase_url(url: &Url, from: &Url, to: &Url) -> Option<Url> {
+    let from = join_endpoint(from, "").ok()?;
+    let path = url.as_str().strip_prefix(from.as_str())?;
+    join_endpoint(to, path).ok()
+}
+
+/// Scales `embedding` to unit length, unless it has none.
+fn normalize(embedding: &mut [f32]) {
+    let norm = embedding.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm > 0.0 && norm.is_finite() {
+        embedding.iter_mut().for_each(|x| *x /= norm);
+    }
+}
+
+/// Extracts the inference result from a response body in the given flavor,
+/// ignoring any fields Spin doesn't use.
+fn parse_infer_response(
+    api_flavor: ApiFlavor,
+    body: &serde_json::Value,
+    endpoint: &str,
+) -> Result<DetailedInferencingResult, wasi_llm::Error> {
+    let deserialize_error = |err: serde_json::Error| {
+        wasi_llm::Error::RuntimeError(format!(
+            "Failed to deserialize response for \"{endpoint}\": {err}"
+        ))
+    };
+    match api_flavor {
+        ApiFlavor::SpinCustom => {
+            let mut val = InferResponseBody::deserialize(body).map_err(deserialize_error)?;
+            Ok(DetailedInferencingResult {
+                finish_reason: val.finish_reason.take(),
+                result: val.into(),
+            })
         }
+        ApiFlavor::OpenAi => openai::CompletionResponse::deserialize(body)
+            .map_err(deserialize_error)?
+            .into_result(),
     }
 }
 
-impl RemoteHttpLlmEngine {
-    pub fn new(url: Url, auth_token: String) -> Self {
-        RemoteHttpLlmEngine {
-            url,
-            auth_token,
-            client: None,
+fn record_inference_usage(model: &str, usage: &wasi_llm::InferencingUsage) {
+    spin_telemetry::metrics::monotonic_counter!(
+        spin.llm_prompt_tokens = u64::from(usage.prompt_token_count),
+        model = model
+    );
+    spin_telemetry::metrics::monotonic_counter!(
+        spin.llm_generated_tokens = u64::from(usage.generated_token_count),
+        model = model
+    );
+}
+
+/// Checks that all the embeddings in a response have the same, nonzero, length
+/// and that it is `expected_dim` if given, so that a misbehaving backend can't
+/// corrupt a vector index.
+fn check_embedding_dimensions(
+    embeddings: &[Vec<f32>],
+    expected_dim: Option<usize>,
+) -> Result<(), wasi_llm::Error> {
+    let Some(first) = embeddings.first() else {
+        return Ok(());
+    };
+    let expected_dim = expected_dim.unwrap_or(first.len());
+    if expected_dim == 0 {
+        return Err(wasi_llm::Error::RuntimeError(
+            "Backend returned an empty embedding".to_string(),
+        ));
+    }
+    if let Some((index, embedding)) = embeddings
+        .iter()
+        .enumerate()
+        .find(|(_, e)| e.len() != expected_dim)
+    {
+        return Err(wasi_llm::Error::RuntimeError(format!(
+            "Backend returned an embedding of dimension {} at index {index}, expected {expected_dim}",
+            embedding.len()
+        )));
+    }
+    Ok(())
+}
+
+/// Turns an unsuccessful response into an error carrying the server's
+/// explanation, so that problems such as an overlong prompt are reported
+/// rather than surfacing as a failure to deserialize the response.
+async fn check_status(operation: &str, resp: Response) -> Result<Response, wasi_llm::Error> {
+    let status = resp.status();
+    if status.is_success() {
+        return Ok(resp);
+    }
+    let retry_after = retry_after(&resp);
+    let body = resp.text().await.unwrap_or_default();
+    let message = error_message(&body)
+        .or_else(|| status.canonical_reason().map(str::to_owned))
+        .unwrap_or_default();
+    if status == StatusCode::TOO_MANY_REQUESTS {
+        // The WIT error type has no rate limiting case, so the retry hint is
+        // given in a consistent form that callers can recognise.
+        let retry_after = retry_after
+            .map(|delay| format!("; retry after {}s", delay.as_secs()))
+            .unwrap_or_default();
+        return Err(wasi_llm::Error::RuntimeError(format!(
+            "{operation} rate limited (429){retry_after}: {message}"
+        )));
+    }
+    Err(wasi_llm::Error::RuntimeError(format!(
+        "{operation} failed ({}): {message}",
+        status.as_u16()
+    )))
+}
+
+/// Parses a response's `Retry-After` header, which may give either a number
+/// of seconds or an HTTP date.
+fn retry_after(resp: &Response) -> Option<Duration> {
+    let value = resp.headers().get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let date = httpdate::parse_http_date(value).ok()?;
+    Some(
+        date.duration_since(std::time::SystemTime::now())
+            .unwrap_or_default(),
+    )
+}
+
+/// Extracts the message from an error response body. Backends variously send
+/// `{"error": "..."}`, OpenAI-style `{"error": {"message": "..."}}` or plain
+/// text.
+fn error_message(body: &str) -> Option<String> {
+    let body = body.trim();
+    if body.is_empty() {
+        return None;
+    }
+    let Ok(json) = serde_json::from_str::<serde_json::Value>(body) else {
+        return Some(body.to_owned());
+    };
+    let error = json.get("error").unwrap_or(&json);
+    let message = error.get("message").unwrap_or(error);
+    match message {
+        serde_json::Value::String(message) => Some(message.clone()),
+        _ => Some(body.to_owned()),
+    }
+}
+
+fn error_detail(err: wasi_llm::Error) -> String {
+    match err {
+        wasi_llm::Error::ModelNotSupported => "model not supported".to_string(),
+        wasi_llm::Error::RuntimeError(message) | wasi_llm::Error::InvalidInput(message) => message,
+    }
+}
+
+/// Generates a random (version 4) UUID to identify a request.
+fn new_request_id() -> String {
+    let bytes: [u8; 16] = rand::random();
+    let hex = |range: std::ops::Range<usize>| {
+        bytes[range]
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect::<String>()
+    };
+    let version = 0x4000 | (u16::from_be_bytes([bytes[6], bytes[7]]) & 0x0fff);
+    let variant = 0x8000 | (u16::from_be_bytes([bytes[8], bytes[9]]) & 0x3fff);
+    format!(
+        "{}-{}-{version:04x}-{variant:04x}-{}",
+        hex(0..4),
+        hex(4..6),
+        hex(10..16)
+    )
+}
+
+/// Adds the ID of the request that failed to an error's message.
+fn tag_request_id(err: wasi_llm::Error, request_id: &str) -> wasi_llm::Error {
+    match err {
+        wasi_llm::Error::RuntimeError(message) => {
+            wasi_llm::Error::RuntimeError(format!("{message} (request id: {request_id})"))
+        }
+        wasi_llm::Error::InvalidInput(message) => {
+            wasi_llm::Error::InvalidInput(format!("{message} (request id: {request_id})"))
+        }
+        err => err,
+    }
+}
+
+fn request_error(endpoint: &str, err: reqwest::Error) -> wasi_llm::Error {
+    if err.is_timeout() {
+        wasi_llm::Error::RuntimeError(format!("{endpoint} request timed out"))
+    } else {
+        wasi_llm::Error::RuntimeError(format!("{endpoint} request error: {err}"))
+    }
+}
+
+/// Parses a server-sent-events response body into inferencing chunks.
+///
+/// Each event's `data` is a JSON object in the backend's `api_flavor` which
+/// may carry generated text and, in the terminal event, the usage for the
+/// whole inference. The stream ends when the body does or when a `[DONE]`
+/// event is received.
+fn event_stream<S, B>(
+    body: S,
+    api_flavor: ApiFlavor,
+    endpoint: String,
+    permit: Option<OwnedSemaphorePermit>,
+) -> impl Stream<Item = Result<InferencingChunk, PartialInferencingError>>
+where
+    S: Stream<Item = reqwest::Result<B>> + Send + Unpin + 'static,
+    B: AsRef<[u8]>,
+{
+    struct State<S> {
+        body: S,
+        api_flavor: ApiFlavor,
+        endpoint: String,
+        _permit: Option<OwnedSemaphorePermit>,
+        buffer: Vec<u8>,
+        pending: VecDeque<InferencingChunk>,
+        /// The text yielded so far, reported if the stream fails.
+        text: String,
+        done: bool,
+    }
+
+    impl<S> State<S> {
+        fn fail(&mut self, source: wasi_llm::Error) -> PartialInferencingError {
+            self.done = true;
+            PartialInferencingError {
+                text: std::mem::take(&mut self.text),
+                source,
+            }
+        }
+    }
+
+    let state = State {
+        body,
+        api_flavor,
+        endpoint,
+        _permit: permit,
+        buffer: Vec::new(),
+        pending: VecDeque::new(),
+        text: String::new(),
+        done: false,
+    };
+
+    futures::stream::unfold(state, |mut state| async move {
+        loop {
+            if let Some(chunk) = state.pending.pop_front() {
+                if let InferencingChunk::Text(text) = &chunk {
+                    state.text.push_str(text);
+                }
+                return Some((Ok(chunk), state));
+            }
+            if state.done {
+                return None;
+            }
+            if let Some(pos) = state.buffer.iter().position(|b| *b == b'\n') {
+                let line: Vec<u8> = state.buffer.drain(..=pos).collect();
+                if let Err(err) = parse_event_line(
+                    &line,
+                    &state.endpoint,
+                    state.api_flavor,
+                    &mut state.pending,
+                    &mut state.done,
+                ) {
+                    let err = state.fail(err);
+                    return Some((Err(err), state));
+                }
+                continue;
+            }
+            match state.body.next().await {
+                Some(Ok(bytes)) => state.buffer.extend_from_slice(bytes.as_ref()),
+                Some(Err(err)) => {
+                    let err = request_error(&state.endpoint, err);
+                    let err = state.fail(err);
+                    return Some((Err(err), state));
+                }
+                None => {
+                    state.done = true;
+                    let line = std::mem::take(&mut state.buffer);
+                    if let Err(err) = parse_event_line(
+                        &line,
+                        &state.endpoint,
+                        state.api_flavor,
+                        &mut state.pending,
+                        &mut state.done,
+                    ) {
+                        let err = state.fail(err);
+                        return Some((Err(err), state));
+                    }
+                }
+            }
         }
+    })
+}
+
+/// Parses a single line of a server-sent-events stream, queuing any chunks it
+/// contains. Lines other than `data` fields (comments, event names, blank
+/// separators) are ignored.
+fn parse_event_line(
+    line: &[u8],
+    endpoint: &str,
+    api_flavor: ApiFlavor,
+    pending: &mut VecDeque<InferencingChunk>,
+    done: &mut bool,
+) -> Result<(), wasi_llm::Error> {
+    let line = String::from_utf8_lossy(line);
+    let Some(data) = line.trim_end_matches(['\r', '\n']).strip_prefix("data:") else {
+        return Ok(());
+    };
+    let data = data.trim();
+    if data.is_empty() {
+        return Ok(());
+    }
+    if data == "[DONE]" {
+        *done = true;
+        return Ok(());
+    }
+    let deserialize_error = |err: serde_json::Error| {
+        wasi_llm::Error::RuntimeError(format!(
+            "Failed to deserialize streaming response for \"{endpoint}\": {err}"
+        ))
+    };
+    let (text, usage) = match api_flavor {
+        ApiFlavor::SpinCustom => {
+            let event: InferStreamEvent = serde_json::from_str(data).map_err(deserialize_error)?;
+            let usage = event.usage.map(|usage| wasi_llm::InferencingUsage {
+                prompt_token_count: usage.prompt_token_count,
+                generated_token_count: usage.generated_token_count,
+            });
+            (event.text, usage)
+        }
+        ApiFlavor::OpenAi => {
+            let event: openai::CompletionResponse =
+                serde_json::from_str(data).map_err(deserialize_error)?;
+            (event.text().map(str::to_owned), event.usage())
+        }
+    };
+    if let Some(text) = text.filter(|t| !t.is_empty()) {
+        pending.push_back(InferencingChunk::Text(text));
+    }
+    if let Some(usage) = usage {
+        pending.push_back(InferencingChunk::Usage(usage));
+        *done = true;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn collect_stream(
+        parts: Vec<&'static str>,
+    ) -> Vec<Result<InferencingChunk, PartialInferencingError>> {
+        collect_flavored_stream(ApiFlavor::SpinCustom, parts)
+    }
+
+    fn collect_flavored_stream(
+        api_flavor: ApiFlavor,
+        parts: Vec<&'static str>,
+    ) -> Vec<Result<InferencingChunk, PartialInferencingError>> {
+        let body = futures::stream::iter(parts.into_iter().map(Ok::<_, reqwest::Error>));
+        let endpoint = format!("POST {}", api_flavor.infer_path());
+        futures::executor::block_on(event_stream(body, api_flavor, endpoint, None).collect())
+    }
+
+    #[test]
+    fn retry_delay_grows_exponentially_within_jitter() {
+        let base = Duration::from_millis(100);
+        for attempt in 1..=4 {
+            let window = base * 2u32.pow(attempt - 1);
+            let delay = retry_delay(base, attempt);
+            assert!(
+                delay >= window / 2 && delay <= window,
+                "{delay:?} vs {window:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn retry_delay_is_capped() {
+        let delay = retry_delay(Duration::from_secs(1), 40);
+        assert!(delay <= MAX_RETRY_BACKOFF);
+    }
+
+    #[test]
+    fn event_stream_yields_text_then_usage() {
+        let chunks = collect_stream(vec![
+            "data: {\"text\":\"Hel\"}\n\n: keep-alive\n",
+            "data: {\"te",
+            "xt\":\"lo\"}\n\n",
+            "data: {\"usage\":{\"promptTokenCount\":3,\"generatedTokenCount\":2}}\n\n",
+            "data: {\"text\":\"ignored after usage\"}\n\n",
+        ]);
+        assert_eq!(3, chunks.len());
+        assert!(matches!(&chunks[0], Ok(InferencingChunk::Text(t)) if t == "Hel"));
+        assert!(matches!(&chunks[1], Ok(InferencingChunk::Text(t)) if t == "lo"));
+        assert!(matches!(
+            &chunks[2],
+            Ok(InferencingChunk::Usage(u)) if u.prompt_token_count == 3 && u.generated_token_count == 2
+        ));
+    }
+
+    #[test]
+    fn event_stream_stops_at_done() {
+        let chunks = collect_stream(vec![
+            "data: {\"text\":\"a\"}\ndata: [DONE]\ndata: {\"text\":\"b\"}\n",
+        ]);
+        assert_eq!(1, chunks.len());
+        assert!(matches!(&chunks[0], Ok(InferencingChunk::Text(t)) if t == "a"));
+    }
+
+    #[test]
+    fn event_stream_handles_unterminated_final_line() {
+        let chunks = collect_stream(vec!["data: {\"text\":\"a\"}"]);
+        assert_eq!(1, chunks.len());
+        assert!(matches!(&chunks[0], Ok(InferencingChunk::Text(t)) if t == "a"));
+    }
+
+    #[test]
+    fn event_stream_reports_malformed_events() {
+        let chunks = collect_stream(vec![
+            "data: {\"text\":\"a\"}\n",
+            "data: {\"text\":\"b\"}\n",
+            "data: not json\n",
+        ]);
+        assert_eq!(3, chunks.len());
+        assert!(matches!(
+            &chunks[2],
+            Err(PartialInferencingError { text, source: wasi_llm::Error::RuntimeError(_) }) if text == "ab"
+        ));
+    }
+
+    #[test]
+    fn openai_event_stream_yields_choice_text_and_usage() {
+        let chunks = collect_flavored_stream(
+            ApiFlavor::OpenAi,
+            vec![
+                "data: {\"choices\":[{\"index\":0,\"text\":\"Hel\"}]}\n\n",
+                "data: {\"choices\":[{\"index\":0,\"text\":\"lo\"}]}\n\n",
+                "data: {\"choices\":[],\"usage\":{\"prompt_tokens\":3,\"completion_tokens\":2,\"total_tokens\":5}}\n\n",
+                "data: [DONE]\n\n",
+            ],
+        );
+        assert_eq!(3, chunks.len());
+        assert!(matches!(&chunks[0], Ok(InferencingChunk::Text(t)) if t == "Hel"));
+        assert!(matches!(&chunks[1], Ok(InferencingChunk::Text(t)) if t == "lo"));
+        assert!(matches!(
+            &chunks[2],
+            Ok(InferencingChunk::Usage(u)) if u.prompt_token_count == 3 && u.generated_token_count == 2
+        ));
+    }
+
+    #[test]
+    fn openai_completion_request_omits_spin_only_options() {
+        let body = serde_json::to_value(openai::CompletionRequest {
+            model: "llama",
+            prompt: "hi",
+            max_tokens: 10,
+            temperature: 0.5,
+            top_p: 0.5,
+            stop: &[],
+            seed: None,
+            presence_penalty: None,
+            frequency_penalty: None,
+            stream: false,
+        })
+        .unwrap();
+        assert_eq!(
+            json!({"model": "llama", "prompt": "hi", "max_tokens": 10, "temperature": 0.5, "top_p": 0.5}),
+            body
+        );
+    }
+
+    #[test]
+    fn openai_completion_response_converts_to_result() {
+        let resp: openai::CompletionResponse = serde_json::from_str(
+            r#"{"id":"cmpl-1","object":"text_completion","choices":[{"index":0,"text":"hello","finish_reason":"stop"}],"usage":{"prompt_tokens":4,"completion_tokens":1,"total_tokens":5}}"#,
+        )
+        .unwrap();
+        let detailed = resp.into_result().unwrap();
+        assert_eq!("hello", detailed.result.text);
+        assert_eq!(4, detailed.result.usage.prompt_token_count);
+        assert_eq!(1, detailed.result.usage.generated_token_count);
+        assert_eq!(Some("stop"), detailed.finish_reason.as_deref());
+    }
+
+    #[test]
+    fn openai_embedding_response_is_ordered_by_index() {
+        let resp: openai::EmbeddingResponse = serde_json::from_str(
+            r#"{"object":"list","data":[{"object":"embedding","index":1,"embedding":[2.0]},{"object":"embedding","index":0,"embedding":[1.0]}],"usage":{"prompt_tokens":6,"total_tokens":6}}"#,
+        )
+        .unwrap();
+        let result = resp.into_result();
+        assert_eq!(vec![vec![1.0], vec![2.0]], result.embeddings);
+        assert_eq!(6, result.usage.prompt_token_count);
+    }
+
+    fn auth_headers(auth_scheme: AuthScheme, token: &str) -> HeaderMap {
+        let engine = RemoteHttpLlmEngine::new(
+            Url::parse("http://localhost:3000").unwrap(),
+            token.to_owned(),
+        )
+        .with_auth_scheme(auth_scheme);
+        let mut headers = HeaderMap::new();
+        engine.apply_auth(&mut headers).unwrap();
+        headers
+    }
+
+    #[test]
+    fn auth_schemes_set_expected_headers() {
+        let headers = auth_headers(AuthScheme::Bearer, "tok");
+        assert_eq!("bearer tok", headers["authorization"]);
+
+        let headers = auth_headers(AuthScheme::ApiKeyHeader("api-key".to_owned()), "tok");
+        assert_eq!("tok", headers["api-key"]);
+        assert!(!headers.contains_key("authorization"));
+
+        let headers = auth_headers(AuthScheme::Basic, "user:pass");
+        assert_eq!("Basic dXNlcjpwYXNz", headers["authorization"]);
+
+        let headers = auth_headers(AuthScheme::None, "tok");
+        assert!(headers.is_empty());
+    }
+
+    #[test]
+    fn models_response_accepts_bare_and_wrapped_lists() {
+        let expected = vec![
+            ModelInfo {
+                id: "llama2-chat".to_owned(),
+                context_length: Some(4096),
+            },
+            ModelInfo {
+                id: "all-minilm-l6-v2".to_owned(),
+                context_length: None,
+            },
+        ];
+
+        let bare = r#"[{"id":"llama2-chat","context_length":4096},{"id":"all-minilm-l6-v2"}]"#;
+        let Ok(ModelsResponseBody::List(models)) = serde_json::from_str(bare) else {
+            panic!("bare list should deserialize");
+        };
+        assert_eq!(expected, models);
+
+        let wrapped = r#"{"object":"list","data":[{"id":"llama2-chat","object":"model","context_length":4096},{"id":"all-minilm-l6-v2","object":"model"}]}"#;
+        let Ok(ModelsResponseBody::Wrapped { data: models }) = serde_json::from_str(wrapped) else {
+            panic!("wrapped list should deserialize");
+        };
+        assert_eq!(expected, models);
+    }
+
+    #[test]
+    fn clones_share_a_client() {
+        let engine = RemoteHttpLlmEngine::new(
+            Url::parse("http://localhost:3000").unwrap(),
+            "tok".to_owned(),
+        );
+        let clone = engine.clone();
+        engine.client().unwrap();
+        assert!(clone.client.get().is_some());
+
+        let reconfigured = clone.with_timeout(Duration::from_secs(1));
+        assert!(reconfigured.client.get().is_none());
+    }
+
+    #[test]
+    fn error_message_is_extracted_from_common_shapes() {
+        assert_eq!(
+            Some("context length exceeded"),
+            error_message(r#"{"error":"context length exceeded"}"#).as_deref()
+        );
+        assert_eq!(
+            Some("model not found"),
+            error_message(
+                r#"{"error":{"message":"model not found","type":"invalid_request_error"}}"#
+            )
+            .as_deref()
+        );
+        assert_eq!(
+            Some("bad gateway"),
+            error_message("bad gateway\n").as_deref()
+        );
+        assert_eq!(
+            Some(r#"{"detail":"nope"}"#),
+            error_message(r#"{"detail":"nope"}"#).as_deref()
+        );
+        assert_eq!(None, error_message("  "));
+    }
+
+    #[test]
+    fn stop_sequences_and_seed_are_sent_when_set() {
+        let params = wasi_llm::InferencingParams {
+            max_tokens: 100,
+            repeat_penalty: 1.1,
+            repeat_penalty_last_n_token_count: 64,
+            temperature: 0.8,
+            top_k: 40,
+            top_p: 0.9,
+        };
+        let engine = RemoteHttpLlmEngine::new(
+            Url::parse("http://localhost:3000").unwrap(),
+            "tok".to_owned(),
+        );
+
+        let body: serde_json::Value =
+            serde_json::from_str(&engine.infer_request_body("m", "p", &params, false).unwrap())
+                .unwrap();
+        assert!(body["options"].get("stop").is_none());
+        assert!(body["options"].get("seed").is_none());
+
+        let engine = engine.with_stop_sequences(["\n\n"]).with_seed(42);
+        let body: serde_json::Value =
+            serde_json::from_str(&engine.infer_request_body("m", "p", &params, false).unwrap())
+                .unwrap();
+        assert_eq!(json!(["\n\n"]), body["options"]["stop"]);
+        assert_eq!(json!(42), body["options"]["seed"]);
+
+        let engine = engine.with_api_flavor(ApiFlavor::OpenAi);
+        let body: serde_json::Value =
+            serde_json::from_str(&engine.infer_request_body("m", "p", &params, false).unwrap())
+                .unwrap();
+        assert_eq!(json!(["\n\n"]), body["stop"]);
+        assert_eq!(json!(42), body["seed"]);
+    }
+
+    #[test]
+    fn concurrency_limit_is_shared_by_clones() {
+        let engine = RemoteHttpLlmEngine::new(
+            Url::parse("http://localhost:3000").unwrap(),
+            "tok".to_owned(),
+        )
+        .with_concurrency_limit(1);
+        let clone = engine.clone();
+
+        futures::executor::block_on(async {
+            let permit = engine.acquire_permit().await.unwrap();
+            assert!(permit.is_some());
+            assert!(futures::poll!(Box::pin(clone.acquire_permit())).is_pending());
+            drop(permit);
+            assert!(clone.acquire_permit().await.unwrap().is_some());
+        });
+    }
+
+    #[test]
+    fn zero_concurrency_limit_still_allows_requests() {
+        let engine = RemoteHttpLlmEngine::new(
+            Url::parse("http://localhost:3000").unwrap(),
+            "tok".to_owned(),
+        )
+        .with_concurrency_limit(0);
+
+        let permit = futures::executor::block_on(async {
+            futures::poll!(Box::pin(engine.acquire_permit()))
+        });
+        assert!(matches!(permit, std::task::Poll::Ready(Ok(Some(_)))));
+    }
+
+    #[test]
+    fn endpoint_override_shares_client() {
+        let engine = RemoteHttpLlmEngine::new(
+            Url::parse("http://localhost:3000").unwrap(),
+            "tok".to_owned(),
+        );
+        let other = engine.with_endpoint(Url::parse("http://other:8080").unwrap());
+        assert_eq!("localhost", engine.url.host_str().unwrap());
+        assert_eq!("other", other.url.host_str().unwrap());
+        assert_eq!(engine.auth_source, other.auth_source);
+
+        other.client().unwrap();
+        assert!(engine.client.get().is_some());
+    }
+
+    #[test]
+    fn embedding_dimensions_must_be_consistent() {
+        assert!(check_embedding_dimensions(&[], Some(3)).is_ok());
+        assert!(check_embedding_dimensions(&[vec![1.0; 3], vec![2.0; 3]], None).is_ok());
+        assert!(check_embedding_dimensions(&[vec![1.0; 3], vec![2.0; 3]], Some(3)).is_ok());
+
+        assert!(check_embedding_dimensions(&[vec![1.0; 3], vec![2.0; 2]], None).is_err());
+        assert!(check_embedding_dimensions(&[vec![1.0; 3]], Some(4)).is_err());
+        assert!(check_embedding_dimensions(&[vec![], vec![]], None).is_err());
+    }
+
+    #[test]
+    fn invalid_proxy_is_reported() {
+        let engine = RemoteHttpLlmEngine::new(
+            Url::parse("http://localhost:3000").unwrap(),
+            "tok".to_owned(),
+        );
+        assert!(engine
+            .clone()
+            .with_proxy("http://proxy.internal:3128")
+            .client()
+            .is_ok());
+        assert!(engine.with_proxy("not a url").client().is_err());
+    }
+
+    #[test]
+    fn invalid_user_agent_is_reported() {
+        let engine = RemoteHttpLlmEngine::new(
+            Url::parse("http://localhost:3000").unwrap(),
+            "tok".to_owned(),
+        );
+        assert!(DEFAULT_USER_AGENT.starts_with("spin-llm-remote-http/"));
+        assert!(engine
+            .clone()
+            .with_user_agent("my-app/1.0")
+            .client()
+            .is_ok());
+        assert!(engine.with_user_agent("bad\nagent").client().is_err());
+    }
+
+    #[test]
+    fn finish_reason_is_optional_in_responses() {
+        let body: InferResponseBody = serde_json::from_str(
+            r#"{"text":"hi","usage":{"promptTokenCount":1,"generatedTokenCount":1},"finishReason":"length"}"#,
+        )
+        .unwrap();
+        assert_eq!(Some("length"), body.finish_reason.as_deref());
+
+        let body: InferResponseBody = serde_json::from_str(
+            r#"{"text":"hi","usage":{"promptTokenCount":1,"generatedTokenCount":1}}"#,
+        )
+        .unwrap();
+        assert_eq!(None, body.finish_reason);
+    }
+
+    #[test]
+    fn injected_client_is_used_until_reconfigured() {
+        let url = Url::parse("http://localhost:3000").unwrap();
+        let engine = RemoteHttpLlmEngine::with_client(url, "tok".to_owned(), Client::new());
+        assert!(engine.client.get().is_some());
+
+        let engine = engine.with_proxy("http://proxy.internal:3128");
+        assert!(engine.client.get().is_none());
+    }
+
+    #[test]
+    fn request_paths_can_be_overridden() {
+        let engine = RemoteHttpLlmEngine::new(
+            Url::parse("http://localhost:3000").unwrap(),
+            "tok".to_owned(),
+        );
+        assert_eq!("/infer", engine.infer_path());
+        assert_eq!("/embed", engine.embed_path());
+
+        let engine = engine
+            .with_api_flavor(ApiFlavor::OpenAi)
+            .with_embed_path("/api/embeddings");
+        assert_eq!("/v1/completions", engine.infer_path());
+        assert_eq!("/api/embeddings", engine.embed_path());
+
+        let engine = engine.with_infer_path("/api/generate");
+        assert_eq!(
+            "http://localhost:3000/api/generate",
+            engine.endpoint_url(engine.infer_path()).unwrap().as_str()
+        );
+    }
+
+    #[test]
+    fn endpoint_urls_keep_base_path_prefix() {
+        let cases = [
+            (
+                "http://localhost:3000",
+                "/infer",
+                "http://localhost:3000/infer",
+            ),
+            (
+                "http://localhost:3000/",
+                "/infer",
+                "http://localhost:3000/infer",
+            ),
+            ("https://host/llm", "/infer", "https://host/llm/infer"),
+            ("https://host/llm/", "/infer", "https://host/llm/infer"),
+            ("https://host/llm", "infer", "https://host/llm/infer"),
+            (
+                "https://host/a/b/",
+                "/v1/embeddings",
+                "https://host/a/b/v1/embeddings",
+            ),
+        ];
+        for (base, path, expected) in cases {
+            let engine = RemoteHttpLlmEngine::new(Url::parse(base).unwrap(), "tok".to_owned());
+            assert_eq!(
+                expected,
+                engine.endpoint_url(path).unwrap().as_str(),
+                "{base} + {path}"
+            );
+        }
+    }
+
+    #[test]
+    fn chat_completion_round_trips() {
+        let messages = [
+            ChatMessage::new("system", "Be brief."),
+            ChatMessage::new("user", "Hi"),
+        ];
+        let body = serde_json::to_value(openai::ChatCompletionRequest {
+            model: "llama",
+            messages: &messages,
+            max_tokens: 10,
+            temperature: 0.5,
+            top_p: 0.5,
+            stop: &[],
+            seed: None,
+            presence_penalty: None,
+            frequency_penalty: None,
+        })
+        .unwrap();
+        assert_eq!(
+            json!([{"role": "system", "content": "Be brief."}, {"role": "user", "content": "Hi"}]),
+            body["messages"]
+        );
+
+        let resp: openai::ChatCompletionResponse = serde_json::from_str(
+            r#"{"choices":[{"index":0,"message":{"role":"assistant","content":"Hello!"},"finish_reason":"stop"}],"usage":{"prompt_tokens":9,"completion_tokens":2,"total_tokens":11}}"#,
+        )
+        .unwrap();
+        let detailed = resp.into_result().unwrap();
+        assert_eq!("Hello!", detailed.result.text);
+        assert_eq!(9, detailed.result.usage.prompt_token_count);
+        assert_eq!(Some("stop"), detailed.finish_reason.as_deref());
+    }
+
+    #[test]
+    fn large_bodies_are_compressed_when_enabled() {
+        use flate2::read::GzDecoder;
+        use std::io::Read;
+
+        let engine = RemoteHttpLlmEngine::new(
+            Url::parse("http://localhost:3000").unwrap(),
+            "tok".to_owned(),
+        );
+        let client = engine.client().unwrap();
+        let large = "x".repeat(COMPRESSION_THRESHOLD);
+        let build = |engine: &RemoteHttpLlmEngine, body: &str| {
+            engine
+                .attach_body(client.post("http://localhost:3000/embed"), body.to_owned())
+                .unwrap()
+                .build()
+                .unwrap()
+        };
+
+        let request = build(&engine, &large);
+        assert!(request.headers().get("content-encoding").is_none());
+
+        let engine = engine.with_request_compression(true);
+        let request = build(&engine, "small");
+        assert!(request.headers().get("content-encoding").is_none());
+
+        let request = build(&engine, &large);
+        assert_eq!("gzip", request.headers()["content-encoding"]);
+        let compressed = request.body().unwrap().as_bytes().unwrap();
+        assert!(compressed.len() < large.len());
+        let mut decompressed = String::new();
+        GzDecoder::new(compressed)
+            .read_to_string(&mut decompressed)
+            .unwrap();
+        assert_eq!(large, decompressed);
+    }
+
+    #[test]
+    fn retry_after_accepts_seconds_and_dates() {
+        let response = |value: &str| {
+            Response::from(
+                http::Response::builder()
+                    .status(429)
+                    .header("retry-after", value)
+                    .body("")
+                    .unwrap(),
+            )
+        };
+        assert_eq!(Some(Duration::from_secs(7)), retry_after(&response("7")));
+        let later = std::time::SystemTime::now() + Duration::from_secs(120);
+        let delay = retry_after(&response(&httpdate::fmt_http_date(later))).unwrap();
+        assert!(delay > Duration::from_secs(100) && delay <= Duration::from_secs(120));
+        assert_eq!(
+            Some(Duration::ZERO),
+            retry_after(&response("Wed, 21 Oct 2015 07:28:00 GMT"))
+        );
+        assert_eq!(None, retry_after(&response("soon")));
+    }
+
+    #[test]
+    fn normalize_scales_to_unit_length() {
+        let mut embedding = vec![3.0, 4.0];
+        normalize(&mut embedding);
+        assert_eq!(vec![0.6, 0.8], embedding);
+
+        let mut zero = vec![0.0, 0.0];
+        normalize(&mut zero);
+        assert_eq!(vec![0.0, 0.0], zero);
+    }
+
+    #[test]
+    fn auth_token_is_read_from_source_per_request() {
+        let dir = std::env::temp_dir().join(format!("spin-llm-auth-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("token");
+        std::fs::write(&path, "first\n").unwrap();
+
+        let engine =
+            RemoteHttpLlmEngine::new(Url::parse("http://localhost:3000").unwrap(), String::new())
+                .with_auth_source(AuthSource::File(path.clone()));
+        let mut headers = HeaderMap::new();
+        engine.apply_auth(&mut headers).unwrap();
+        assert_eq!("bearer first", headers["authorization"]);
+
+        std::fs::write(&path, "second").unwrap();
+        engine.apply_auth(&mut headers).unwrap();
+        assert_eq!("bearer second", headers["authorization"]);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(engine.apply_auth(&mut headers).is_err());
+    }
+
+    #[test]
+    fn urls_are_rebased_onto_fallback() {
+        let primary = Url::parse("https://primary/llm").unwrap();
+        let fallback = Url::parse("http://fallback:8080/").unwrap();
+        let url = join_endpoint(&primary, "/v1/completions").unwrap();
+        assert_eq!(
+            "http://fallback:8080/v1/completions",
+            rebase_url(&url, &primary, &fallback).unwrap().as_str()
+        );
+
+        let elsewhere = Url::parse("https://other/infer").unwrap();
+        assert!(rebase_url(&elsewhere, &primary, &fallback).is_none());
+    }
+
+    #[test]
+    fn raw_response_keeps_vendor_fields() {
+        let body = serde_json::json!({
+            "text": "hi",
+            "usage": {"promptTokenCount": 1, "generatedTokenCount": 1},
+            "logprobs": [-0.5],
+        });
+        let result = parse_infer_response(ApiFlavor::SpinCustom, &body, "POST /infer").unwrap();
+        assert_eq!("hi", result.result.text);
+        assert_eq!(serde_json::json!([-0.5]), body["logprobs"]);
+
+        assert!(matches!(
+            parse_infer_response(ApiFlavor::OpenAi, &body, "POST /infer"),
+            Err(wasi_llm::Error::RuntimeError(_))
+        ));
+    }
+
+    #[test]
+    fn penalties_are_sent_when_set() {
+        let params = wasi_llm::InferencingParams {
+            max_tokens: 100,
+            repeat_penalty: 1.1,
+            repeat_penalty_last_n_token_count: 64,
+            temperature: 0.8,
+            top_k: 40,
+            top_p: 0.9,
+        };
+        let engine = RemoteHttpLlmEngine::new(
+            Url::parse("http://localhost:3000").unwrap(),
+            "tok".to_owned(),
+        );
+        let body: serde_json::Value =
+            serde_json::from_str(&engine.infer_request_body("m", "p", &params, false).unwrap())
+                .unwrap();
+        assert!(body["options"].get("presencePenalty").is_none());
+        assert!(body["options"].get("frequencyPenalty").is_none());
+
+        let engine = engine
+            .with_presence_penalty(0.5)
+            .with_frequency_penalty(0.25);
+        let body: serde_json::Value =
+            serde_json::from_str(&engine.infer_request_body("m", "p", &params, false).unwrap())
+                .unwrap();
+        assert_eq!(json!(0.5), body["options"]["presencePenalty"]);
+        assert_eq!(json!(0.25), body["options"]["frequencyPenalty"]);
+
+        let engine = engine.with_api_flavor(ApiFlavor::OpenAi);
+        let body: serde_json::Value =
+            serde_json::from_str(&engine.infer_request_body("m", "p", &params, false).unwrap())
+                .unwrap();
+        assert_eq!(json!(0.5), body["presence_penalty"]);
+        assert_eq!(json!(0.25), body["frequency_penalty"]);
+    }
+
+    /// Serves a single request on a Unix socket named after `name`, replying
+    /// with `response_body` as JSON. The server task returns the request it
+    /// received.
+    #[cfg(unix)]
+    fn serve_once(
+        name: &str,
+        response_body: &'static str,
+    ) -> (Url, std::path::PathBuf, tokio::task::JoinHandle<String>) {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let path =
+            std::env::temp_dir().join(format!("spin-llm-{name}-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let listener = tokio::net::UnixListener::bind(&path).unwrap();
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0; 4096];
+            loop {
+                let len = stream.read(&mut buf).await.unwrap();
+                if len == 0 {
+                    break;
+                }
+                request.extend_from_slice(&buf[..len]);
+                let text = String::from_utf8_lossy(&request);
+                let Some((head, body)) = text.split_once("\r\n\r\n") else {
+                    continue;
+                };
+                let content_length = head
+                    .lines()
+                    .find_map(|line| {
+                        line.to_ascii_lowercase()
+                            .strip_prefix("content-length: ")
+                            .map(str::to_owned)
+                    })
+                    .map_or(0, |len| len.trim().parse().unwrap());
+                if body.len() >= content_length {
+                    break;
+                }
+            }
+            let response = format!(
+                "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\n\r\n{response_body}",
+                response_body.len()
+            );
+            stream.write_all(response.as_bytes()).await.unwrap();
+            String::from_utf8_lossy(&request).into_owned()
+        });
+        let url = Url::parse(&format!("unix://{}", path.display())).unwrap();
+        (url, path, server)
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn requests_can_be_sent_over_unix_socket() {
+        let (url, path, server) = serve_once(
+            "infer",
+            r#"{"text":"hi","usage":{"promptTokenCount":1,"generatedTokenCount":1}}"#,
+        );
+        let mut engine = RemoteHttpLlmEngine::new(url, "tok".to_owned());
+        let params = wasi_llm::InferencingParams {
+            max_tokens: 100,
+            repeat_penalty: 1.1,
+            repeat_penalty_last_n_token_count: 64,
+            temperature: 0.8,
+            top_k: 40,
+            top_p: 0.9,
+        };
+        let result = engine.infer("m".into(), "p".into(), params).await.unwrap();
+        assert_eq!("hi", result.text);
+
+        let request = server.await.unwrap();
+        assert!(request.starts_with("POST /infer HTTP/1.1\r\n"), "{request}");
+        assert!(request.contains(DEFAULT_USER_AGENT));
+        assert!(request.contains("x-request-id: "), "{request}");
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn warmup_requests_a_single_token() {
+        let (url, path, server) = serve_once(
+            "warmup",
+            r#"{"text":"hi","usage":{"promptTokenCount":1,"generatedTokenCount":1}}"#,
+        );
+        let mut engine = RemoteHttpLlmEngine::new(url, "tok".to_owned());
+        engine.warmup("llama2-chat").await.unwrap();
+
+        let request = server.await.unwrap();
+        assert!(request.contains(r#""model":"llama2-chat""#), "{request}");
+        assert!(request.contains(r#""maxTokens":1,"#), "{request}");
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn cached_embeddings_are_not_requested_again() {
+        let mut engine = RemoteHttpLlmEngine::new(
+            Url::parse("http://localhost:3000").unwrap(),
+            "tok".to_owned(),
+        )
+        .with_embedding_cache(8);
+        let cache = engine.embedding_cache.clone().unwrap();
+        cache.insert("m", "a", vec![1.0]);
+        cache.insert("m", "b", vec![2.0]);
+
+        let result = engine
+            .generate_embeddings("m".into(), vec!["b".into(), "a".into(), "b".into()])
+            .await
+            .unwrap();
+        assert_eq!(vec![vec![2.0], vec![1.0], vec![2.0]], result.embeddings);
+        assert_eq!(0, result.usage.prompt_token_count);
+    }
+
+    #[test]
+    fn request_ids_are_uuids_and_tag_errors() {
+        let id = new_request_id();
+        assert_eq!(36, id.len());
+        assert_eq!(Some('4'), id.chars().nth(14));
+        assert_ne!(id, new_request_id());
+
+        let engine = RemoteHttpLlmEngine::new(
+            Url::parse("http://localhost:3000").unwrap(),
+            "tok".to_owned(),
+        )
+        .with_request_id("req-1");
+        let headers = engine.request_headers(&engine.request_id()).unwrap();
+        assert_eq!("req-1", headers[REQUEST_ID_HEADER]);
+
+        let err = tag_request_id(wasi_llm::Error::RuntimeError("boom".to_owned()), "req-1");
+        assert!(matches!(err, wasi_llm::Error::RuntimeError(m) if m == "boom (request id: req-1)"));
+    }
+
+    #[test]
+    fn model_defaults_override_spin_defaults() {
+        let engine = RemoteHttpLlmEngine::new(
+            Url::parse("http://localhost:3000").unwrap(),
+            "tok".to_owned(),
+        )
+        .with_model_defaults(
+            "llama2-chat",
+            ModelDefaults {
+                temperature: Some(0.2),
+                max_tokens: Some(512),
+                ..Default::default()
+            },
+        );
+        let params = engine.default_params("llama2-chat");
+        assert_eq!(0.2, params.temperature);
+        assert_eq!(512, params.max_tokens);
+        assert_eq!(DEFAULT_INFERENCING_PARAMS.top_p, params.top_p);
+        assert_eq!(
+            DEFAULT_INFERENCING_PARAMS.temperature,
+            engine.default_params("codellama").temperature
+        );
     }
 }
@@ -0,0 +1,192 @@
+//! Request and response bodies for backends that speak the OpenAI
+//! `/v1/completions`, `/v1/chat/completions` and `/v1/embeddings` API.
+
+use serde::{Deserialize, Serialize};
+use spin_llm::DetailedInferencingResult;
+use spin_world::v2::llm::{self as wasi_llm};
+
+use crate::ChatMessage;
+
+/// The body of a completion request.
+///
+/// OpenAI has no equivalent of Spin's `top_k` or repeat penalty options, so
+/// those inferencing parameters are not sent. Repetition is instead controlled
+/// by the engine's presence and frequency penalties, when set.
+#[derive(Serialize)]
+pub(crate) struct CompletionRequest<'a> {
+    pub model: &'a str,
+    pub prompt: &'a str,
+    pub max_tokens: u32,
+    pub temperature: f32,
+    pub top_p: f32,
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    pub stop: &'a [String],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub presence_penalty: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub frequency_penalty: Option<f32>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub stream: bool,
+}
+
+/// The body of a completion response, or of a single event in a streamed
+/// completion response.
+#[derive(Deserialize)]
+pub(crate) struct CompletionResponse {
+    #[serde(default)]
+    choices: Vec<CompletionChoice>,
+    #[serde(default)]
+    usage: Option<Usage>,
+}
+
+#[derive(Deserialize)]
+struct CompletionChoice {
+    #[serde(default)]
+    text: String,
+    #[serde(default)]
+    finish_reason: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Usage {
+    prompt_tokens: u32,
+    #[serde(default)]
+    completion_tokens: u32,
+}
+
+impl CompletionResponse {
+    /// The text of the first choice, if the response has any.
+    pub fn text(&self) -> Option<&str> {
+        self.choices.first().map(|c| c.text.as_str())
+    }
+
+    pub fn usage(&self) -> Option<wasi_llm::InferencingUsage> {
+        self.usage.as_ref().map(|u| wasi_llm::InferencingUsage {
+            prompt_token_count: u.prompt_tokens,
+            generated_token_count: u.completion_tokens,
+        })
+    }
+
+    pub fn into_result(self) -> Result<DetailedInferencingResult, wasi_llm::Error> {
+        let usage = self.usage().unwrap_or(wasi_llm::InferencingUsage {
+            prompt_token_count: 0,
+            generated_token_count: 0,
+        });
+        let choice = self.choices.into_iter().next().ok_or_else(|| {
+            wasi_llm::Error::RuntimeError("Completion response contained no choices".to_string())
+        })?;
+        Ok(DetailedInferencingResult {
+            result: wasi_llm::InferencingResult {
+                text: choice.text,
+                usage,
+            },
+            finish_reason: choice.finish_reason,
+        })
+    }
+}
+
+/// The body of a chat completion request.
+#[derive(Serialize)]
+pub(crate) struct ChatCompletionRequest<'a> {
+    pub model: &'a str,
+    pub messages: &'a [ChatMessage],
+    pub max_tokens: u32,
+    pub temperature: f32,
+    pub top_p: f32,
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    pub stop: &'a [String],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub presence_penalty: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub frequency_penalty: Option<f32>,
+}
+
+/// The body of a chat completion response.
+#[derive(Deserialize)]
+pub(crate) struct ChatCompletionResponse {
+    #[serde(default)]
+    choices: Vec<ChatChoice>,
+    #[serde(default)]
+    usage: Option<Usage>,
+}
+
+#[derive(Deserialize)]
+struct ChatChoice {
+    message: ChatChoiceMessage,
+    #[serde(default)]
+    finish_reason: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ChatChoiceMessage {
+    /// Absent when the model responds only with tool calls.
+    #[serde(default)]
+    content: Option<String>,
+}
+
+impl ChatCompletionResponse {
+    pub fn into_result(self) -> Result<DetailedInferencingResult, wasi_llm::Error> {
+        let usage = self
+            .usage
+            .map(|u| wasi_llm::InferencingUsage {
+                prompt_token_count: u.prompt_tokens,
+                generated_token_count: u.completion_tokens,
+            })
+            .unwrap_or(wasi_llm::InferencingUsage {
+                prompt_token_count: 0,
+                generated_token_count: 0,
+            });
+        let choice = self.choices.into_iter().next().ok_or_else(|| {
+            wasi_llm::Error::RuntimeError(
+                "Chat completion response contained no choices".to_string(),
+            )
+        })?;
+        Ok(DetailedInferencingResult {
+            result: wasi_llm::InferencingResult {
+                text: choice.message.content.unwrap_or_default(),
+                usage,
+            },
+            finish_reason: choice.finish_reason,
+        })
+    }
+}
+
+/// The body of an embeddings request.
+#[derive(Serialize)]
+pub(crate) struct EmbeddingRequest<'a> {
+    pub model: &'a str,
+    pub input: &'a [String],
+}
+
+/// The body of an embeddings response.
+#[derive(Deserialize)]
+pub(crate) struct EmbeddingResponse {
+    data: Vec<Embedding>,
+    #[serde(default)]
+    usage: Option<Usage>,
+}
+
+#[derive(Deserialize)]
+struct Embedding {
+    #[serde(default)]
+    index: usize,
+    embedding: Vec<f32>,
+}
+
+impl EmbeddingResponse {
+    pub fn into_result(mut self) -> wasi_llm::EmbeddingsResult {
+        // Embeddings are returned with the index of their input, which
+        // servers aren't required to respect when ordering them.
+        self.data.sort_by_key(|e| e.index);
+        wasi_llm::EmbeddingsResult {
+            embeddings: self.data.into_iter().map(|e| e.embedding).collect(),
+            usage: wasi_llm::EmbeddingsUsage {
+                prompt_token_count: self.usage.map(|u| u.prompt_tokens).unwrap_or_default(),
+            },
+        }
+    }
+}
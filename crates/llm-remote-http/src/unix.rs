@@ -0,0 +1,170 @@
+//! Support for backends that listen on a Unix domain socket, such as inference
+//! servers running as a sidecar on the same host.
+//!
+//! reqwest can only connect over TCP, so requests to such backends are built
+//! with reqwest as usual and then sent with a hyper client whose connector
+//! opens the socket.
+
+use reqwest::Url;
+
+/// The base URL that stands in for a `unix://` URL when building requests. The
+/// host is only used for the `Host` header; connections always go to the
+/// socket.
+const SOCKET_BASE_URL: &str = "http://localhost/";
+
+/// Splits a `unix:///path/to/socket` URL into the base URL to build requests
+/// against and a client that sends them over the socket. Other URLs are
+/// returned unchanged, without a client.
+pub(crate) fn resolve(url: Url) -> (Url, Option<UnixClient>) {
+    if url.scheme() == "unix" {
+        // Where sockets are unsupported the URL is kept, so that requests fail
+        // with an unsupported scheme error rather than going elsewhere.
+        if let Some(client) = UnixClient::new(url.path().into()) {
+            let base = Url::parse(SOCKET_BASE_URL).expect("socket base URL should be valid");
+            return (base, Some(client));
+        }
+    }
+    (url, None)
+}
+
+#[cfg(unix)]
+pub(crate) use imp::UnixClient;
+
+#[cfg(unix)]
+mod imp {
+    use std::future::Future;
+    use std::io;
+    use std::path::PathBuf;
+    use std::pin::Pin;
+    use std::sync::Arc;
+    use std::task::{Context, Poll};
+
+    use hyper::client::connect::{Connected, Connection};
+    use hyper::service::Service;
+    use hyper::Uri;
+    use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+    use tokio::net::UnixStream;
+
+    /// Sends requests to a backend listening on a Unix domain socket.
+    /// Clones share a connection pool.
+    #[derive(Clone)]
+    pub(crate) struct UnixClient {
+        client: hyper::Client<UnixConnector, hyper::Body>,
+    }
+
+    impl UnixClient {
+        pub(super) fn new(path: PathBuf) -> Option<Self> {
+            let connector = UnixConnector { path: path.into() };
+            Some(Self {
+                client: hyper::Client::builder().build(connector),
+            })
+        }
+
+        /// Sends `request`, whose body must not be a stream. The response
+        /// body is streamed from the socket as it is read.
+        pub(crate) async fn execute(
+            &self,
+            request: reqwest::Request,
+        ) -> Result<reqwest::Response, hyper::Error> {
+            let body = request
+                .body()
+                .and_then(|body| body.as_bytes())
+                .map(|bytes| hyper::Body::from(bytes.to_vec()))
+                .unwrap_or_else(hyper::Body::empty);
+            let mut builder = http::Request::builder()
+                .method(request.method().clone())
+                .uri(request.url().as_str());
+            if let Some(headers) = builder.headers_mut() {
+                headers.extend(request.headers().clone());
+            }
+            let request = builder
+                .body(body)
+                .expect("request built by reqwest should be valid");
+
+            let (parts, body) = self.client.request(request).await?.into_parts();
+            Ok(http::Response::from_parts(parts, reqwest::Body::wrap_stream(body)).into())
+        }
+    }
+
+    #[derive(Clone)]
+    pub(crate) struct UnixConnector {
+        path: Arc<std::path::Path>,
+    }
+
+    impl Service<Uri> for UnixConnector {
+        type Response = UnixConnection;
+        type Error = io::Error;
+        type Future = Pin<Box<dyn Future<Output = io::Result<UnixConnection>> + Send>>;
+
+        fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, _uri: Uri) -> Self::Future {
+            let path = self.path.clone();
+            Box::pin(async move { Ok(UnixConnection(UnixStream::connect(&*path).await?)) })
+        }
+    }
+
+    pub(crate) struct UnixConnection(UnixStream);
+
+    impl Connection for UnixConnection {
+        fn connected(&self) -> Connected {
+            Connected::new()
+        }
+    }
+
+    impl AsyncRead for UnixConnection {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &mut ReadBuf<'_>,
+        ) -> Poll<io::Result<()>> {
+            Pin::new(&mut self.0).poll_read(cx, buf)
+        }
+    }
+
+    impl AsyncWrite for UnixConnection {
+        fn poll_write(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &[u8],
+        ) -> Poll<io::Result<usize>> {
+            Pin::new(&mut self.0).poll_write(cx, buf)
+        }
+
+        fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Pin::new(&mut self.0).poll_flush(cx)
+        }
+
+        fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Pin::new(&mut self.0).poll_shutdown(cx)
+        }
+    }
+}
+
+#[cfg(not(unix))]
+pub(crate) use imp::UnixClient;
+
+#[cfg(not(unix))]
+mod imp {
+    use std::path::PathBuf;
+
+    /// Unix domain sockets are unavailable on this platform, so there are no
+    /// clients for them.
+    #[derive(Clone)]
+    pub(crate) enum UnixClient {}
+
+    impl UnixClient {
+        pub(super) fn new(_path: PathBuf) -> Option<Self> {
+            None
+        }
+
+        pub(crate) async fn execute(
+            &self,
+            _request: reqwest::Request,
+        ) -> Result<reqwest::Response, hyper::Error> {
+            match *self {}
+        }
+    }
+}
@@ -1,5 +1,7 @@
 pub mod host_component;
 
+use futures::stream::BoxStream;
+use futures::StreamExt;
 use spin_app::MetadataKey;
 use spin_core::async_trait;
 use spin_world::v1::llm::{self as v1};
@@ -11,6 +13,65 @@ pub use crate::host_component::LlmComponent;
 pub const MODEL_ALL_MINILM_L6_V2: &str = "all-minilm-l6-v2";
 pub const AI_MODELS_KEY: MetadataKey<HashSet<String>> = MetadataKey::new("ai_models");
 
+/// The inferencing parameters used when a guest passes none, unless the engine
+/// has its own defaults for the model.
+pub const DEFAULT_INFERENCING_PARAMS: v2::InferencingParams = v2::InferencingParams {
+    max_tokens: 100,
+    repeat_penalty: 1.1,
+    repeat_penalty_last_n_token_count: 64,
+    temperature: 0.8,
+    top_k: 40,
+    top_p: 0.9,
+};
+
+/// The result of an inference, along with details that the WIT interface has
+/// no place for.
+pub struct DetailedInferencingResult {
+    pub result: v2::InferencingResult,
+    /// Why generation stopped, such as `stop` for a stop sequence or end of
+    /// text, or `length` for reaching the token limit, if the engine reports it.
+    pub finish_reason: Option<String>,
+}
+
+/// A piece of output from a streaming inference.
+pub enum InferencingChunk {
+    /// Generated text, in the order it was produced.
+    Text(String),
+    /// Token usage for the whole inference, sent once generation has finished.
+    Usage(v2::InferencingUsage),
+}
+
+/// An error that ended a streaming inference part way through, along with the
+/// text generated before it happened so that callers can salvage it.
+#[derive(Debug)]
+pub struct PartialInferencingError {
+    /// All the text yielded by the stream before the error.
+    pub text: String,
+    pub source: v2::Error,
+}
+
+impl std::fmt::Display for PartialInferencingError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "inference failed after {} bytes of output: {:?}",
+            self.text.len(),
+            self.source
+        )
+    }
+}
+
+impl std::error::Error for PartialInferencingError {}
+
+impl From<PartialInferencingError> for v2::Error {
+    fn from(err: PartialInferencingError) -> Self {
+        err.source
+    }
+}
+
+/// The output of a streaming inference.
+pub type InferencingStream = BoxStream<'static, Result<InferencingChunk, PartialInferencingError>>;
+
 #[async_trait]
 pub trait LlmEngine: Send + Sync {
     async fn infer(
@@ -20,6 +81,67 @@ pub trait LlmEngine: Send + Sync {
         params: v2::InferencingParams,
     ) -> Result<v2::InferencingResult, v2::Error>;
 
+    /// The parameters used for inferences with `model` when the guest passes
+    /// none.
+    ///
+    /// The default implementation returns [`DEFAULT_INFERENCING_PARAMS`] for
+    /// every model.
+    fn default_params(&self, model: &str) -> v2::InferencingParams {
+        let _ = model;
+        DEFAULT_INFERENCING_PARAMS
+    }
+
+    /// Performs an inference, also reporting why generation stopped.
+    ///
+    /// The default implementation reports no finish reason.
+    async fn infer_detailed(
+        &mut self,
+        model: v1::InferencingModel,
+        prompt: String,
+        params: v2::InferencingParams,
+    ) -> Result<DetailedInferencingResult, v2::Error> {
+        let result = self.infer(model, prompt, params).await?;
+        Ok(DetailedInferencingResult {
+            result,
+            finish_reason: None,
+        })
+    }
+
+    /// Performs an inference, yielding generated text as it becomes available.
+    ///
+    /// The default implementation waits for the whole inference to complete
+    /// and yields it as a single chunk.
+    async fn infer_stream(
+        &mut self,
+        model: v1::InferencingModel,
+        prompt: String,
+        params: v2::InferencingParams,
+    ) -> Result<InferencingStream, v2::Error> {
+        let result = self.infer(model, prompt, params).await?;
+        let chunks = [
+            Ok(InferencingChunk::Text(result.text)),
+            Ok(InferencingChunk::Usage(result.usage)),
+        ];
+        Ok(futures::stream::iter(chunks).boxed())
+    }
+
+    /// Performs an inference for each of several prompts with the same
+    /// parameters, returning the results in the order of the prompts.
+    ///
+    /// The default implementation performs the inferences one at a time.
+    async fn infer_batch(
+        &mut self,
+        model: v1::InferencingModel,
+        prompts: Vec<String>,
+        params: v2::InferencingParams,
+    ) -> Result<Vec<v2::InferencingResult>, v2::Error> {
+        let mut results = Vec::with_capacity(prompts.len());
+        for prompt in prompts {
+            results.push(self.infer(model.clone(), prompt, params).await?);
+        }
+        Ok(results)
+    }
+
     async fn generate_embeddings(
         &mut self,
         model: v2::EmbeddingModel,
@@ -43,20 +165,8 @@ impl v2::Host for LlmDispatch {
         if !self.allowed_models.contains(&model) {
             return Err(access_denied_error(&model));
         }
-        self.engine
-            .infer(
-                model,
-                prompt,
-                params.unwrap_or(v2::InferencingParams {
-                    max_tokens: 100,
-                    repeat_penalty: 1.1,
-                    repeat_penalty_last_n_token_count: 64,
-                    temperature: 0.8,
-                    top_k: 40,
-                    top_p: 0.9,
-                }),
-            )
-            .await
+        let params = params.unwrap_or_else(|| self.engine.default_params(&model));
+        self.engine.infer(model, prompt, params).await
     }
 
     async fn generate_embeddings(
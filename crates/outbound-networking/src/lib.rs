@@ -289,6 +289,7 @@ fn well_known_port(scheme: &str) -> Option<u16> {
         "postgres" => Some(5432),
         "mysql" => Some(3306),
         "redis" => Some(6379),
+        "rediss" => Some(6379),
         "mqtt" => Some(1883),
         "http" => Some(80),
         "https" => Some(443),
@@ -746,6 +747,16 @@ mod test {
         assert!(allowed.allows(&OutboundUrl::parse("example.com:8383", "http").unwrap()));
     }
 
+    #[test]
+    fn test_allowed_hosts_accepts_rediss_without_port() {
+        let allowed =
+            AllowedHostsConfig::parse(&["rediss://redis.example.com"], &dummy_resolver()).unwrap();
+        assert!(allowed.allows(&OutboundUrl::parse("rediss://redis.example.com", "redis").unwrap()));
+        assert!(allowed
+            .allows(&OutboundUrl::parse("rediss://redis.example.com:6379", "redis").unwrap()));
+        assert!(!allowed.allows(&OutboundUrl::parse("redis://redis.example.com", "redis").unwrap()));
+    }
+
     #[test]
     fn test_allowed_hosts_with_trailing_slash() {
         let allowed =
@@ -0,0 +1,674 @@
+//! Connections to a Redis Cluster, which shards keys across several servers.
+//!
+//! Each command is sent to the server that owns the hash slot of its key,
+//! following `MOVED` and `ASK` redirects when the slot has moved. The cluster
+//! names the servers to use, in `CLUSTER SLOTS` and in redirects, so each one
+//! is checked against the allowed hosts before it is connected to.
+
+use std::collections::hash_map::{Entry, HashMap};
+use std::fmt;
+use std::time::Duration;
+
+use redis::{aio::ConnectionLike, Arg, ConnectionAddr, ConnectionInfo, RedisFuture, Value};
+use spin_world::v2_1::redis::Error;
+
+use crate::connection_error;
+
+/// The suffix that marks a single address as a seed for a cluster rather than
+/// a standalone server.
+const CLUSTER_FRAGMENT: &str = "#cluster";
+
+/// The number of hash slots that keys are sharded across.
+const SLOT_COUNT: u16 = 16384;
+
+/// How many redirects to follow for a command before giving up, in case the
+/// servers disagree about which of them owns a slot.
+const MAX_REDIRECTS: usize = 5;
+
+/// Decides whether the server at a `redis://` or `rediss://` URL may be
+/// connected to.
+pub(crate) type AllowedHosts = Box<dyn Fn(&str) -> bool + Send>;
+
+/// Returns the seed addresses if `address` is for a cluster, either because
+/// it lists several comma-separated seeds or because it ends in `#cluster`.
+///
+/// Only a comma followed by another address separates seeds, as a password
+/// may contain one too.
+pub(crate) fn seeds(address: &str) -> Option<Vec<&str>> {
+    if let Some(seed) = address.strip_suffix(CLUSTER_FRAGMENT) {
+        return Some(vec![seed]);
+    }
+    let mut seeds = Vec::new();
+    let mut start = 0;
+    for (comma, _) in address.match_indices(',') {
+        if starts_with_scheme(address[comma + 1..].trim_start()) {
+            seeds.push(address[start..comma].trim());
+            start = comma + 1;
+        }
+    }
+    seeds.push(address[start..].trim());
+    (seeds.len() > 1).then_some(seeds)
+}
+
+/// Returns whether `s` starts with a URL scheme, such as `redis://`.
+fn starts_with_scheme(s: &str) -> bool {
+    s.split_once("://").is_some_and(|(scheme, _)| {
+        scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+            && scheme
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c))
+    })
+}
+
+/// Connects to the cluster that `seeds` belong to, giving up on each server
+/// after `timeout`. Every server is connected to with the credentials and TLS
+/// settings of the first seed.
+///
+/// Fails with `invalid-address` if the cluster has a server that
+/// `is_allowed` rejects.
+pub(crate) async fn connect(
+    seeds: Vec<ConnectionInfo>,
+    is_allowed: AllowedHosts,
+    timeout: Duration,
+) -> Result<ClusterConnection, Error> {
+    let info = seeds.first().cloned().ok_or(Error::InvalidAddress)?;
+    let seeds = seeds
+        .iter()
+        .map(|seed| Node::of(&seed.addr))
+        .collect::<Option<_>>()
+        .ok_or(Error::InvalidAddress)?;
+    let mut cluster = ClusterConnection {
+        info,
+        is_allowed,
+        timeout,
+        seeds,
+        slots: Vec::new(),
+        connections: HashMap::new(),
+        in_flight: None,
+    };
+    let slots = cluster.fetch_slots().await.map_err(connection_error)?;
+    if let Some(node) = cluster.disallowed(&slots) {
+        tracing::warn!("Redis cluster server {node} is not allowed");
+        return Err(Error::InvalidAddress);
+    }
+    cluster.slots = slots;
+    Ok(cluster)
+}
+
+/// A server in a cluster.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct Node {
+    host: String,
+    port: u16,
+}
+
+impl Node {
+    fn of(addr: &ConnectionAddr) -> Option<Self> {
+        match addr {
+            ConnectionAddr::Tcp(host, port) | ConnectionAddr::TcpTls { host, port, .. } => {
+                Some(Self {
+                    host: host.clone(),
+                    port: *port,
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// Parses the `host:port` that a redirect names.
+    fn parse(s: &str) -> Option<Self> {
+        let (host, port) = s.rsplit_once(':')?;
+        Some(Self {
+            host: host
+                .trim_start_matches('[')
+                .trim_end_matches(']')
+                .to_owned(),
+            port: port.parse().ok()?,
+        })
+    }
+}
+
+impl fmt::Display for Node {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.host.contains(':') {
+            write!(f, "[{}]:{}", self.host, self.port)
+        } else {
+            write!(f, "{}:{}", self.host, self.port)
+        }
+    }
+}
+
+/// A range of slots, from `start` to `end` inclusive, and the server that
+/// owns them.
+#[derive(Debug, PartialEq)]
+struct Slots {
+    start: u16,
+    end: u16,
+    node: Node,
+}
+
+pub(crate) struct ClusterConnection {
+    /// The credentials and TLS settings to connect to each server with.
+    info: ConnectionInfo,
+    is_allowed: AllowedHosts,
+    timeout: Duration,
+    seeds: Vec<Node>,
+    /// The server that owns each range of slots, as of the last
+    /// `CLUSTER SLOTS`, or empty if that needs asking again.
+    slots: Vec<Slots>,
+    /// Connections to the servers used so far.
+    connections: HashMap<Node, redis::aio::Connection>,
+    /// The server a request was last sent to, until its reply is read. A
+    /// request abandoned by a timeout leaves its reply unread, where it would
+    /// be taken for the reply to the next one, so that connection is dropped.
+    in_flight: Option<Node>,
+}
+
+impl ClusterConnection {
+    /// Runs `cmd` on the server its key belongs to, following redirects. A
+    /// redirected command hasn't run, so it is safe to send again.
+    async fn request(&mut self, cmd: &redis::Cmd) -> redis::RedisResult<Value> {
+        self.drop_in_flight();
+        let mut node = match route(cmd) {
+            Route::AllPrimaries => {
+                let mut reply = Value::Okay;
+                for node in self.primaries().await? {
+                    reply = self.send(&node, cmd, false).await?;
+                }
+                return Ok(reply);
+            }
+            route => self.node_for(route).await?,
+        };
+        let mut asking = false;
+        for _ in 0..MAX_REDIRECTS {
+            match self.send(&node, cmd, asking).await {
+                Err(e) if matches!(e.kind(), redis::ErrorKind::Moved | redis::ErrorKind::Ask) => {
+                    let Some(target) = e.redirect_node().and_then(|(n, _)| Node::parse(n)) else {
+                        return Err(e);
+                    };
+                    asking = e.kind() == redis::ErrorKind::Ask;
+                    if !asking {
+                        // The slot has moved for good, so others may have too.
+                        self.slots.clear();
+                    }
+                    node = target;
+                }
+                result => return result,
+            }
+        }
+        Err(redis::RedisError::from((
+            redis::ErrorKind::ClientError,
+            "too many cluster redirects",
+        )))
+    }
+
+    /// Runs `pipeline` on the server that the key of its first command with
+    /// one belongs to. Pipelines aren't redirected, as some of their commands
+    /// may already have run.
+    async fn request_pipeline(
+        &mut self,
+        pipeline: &redis::Pipeline,
+        offset: usize,
+        count: usize,
+    ) -> redis::RedisResult<Vec<Value>> {
+        self.drop_in_flight();
+        let route = pipeline
+            .cmd_iter()
+            .map(route)
+            .find(|route| matches!(route, Route::Slot(_)))
+            .unwrap_or(Route::Any);
+        let node = self.node_for(route).await?;
+        self.in_flight = Some(node.clone());
+        let result = match self.connection(&node).await {
+            Ok(conn) => conn.req_packed_commands(pipeline, offset, count).await,
+            Err(e) => Err(e),
+        };
+        self.settle(&node, &result);
+        if matches!(&result, Err(e) if e.kind() == redis::ErrorKind::Moved) {
+            self.slots.clear();
+        }
+        result
+    }
+
+    /// Sends `cmd` to `node`, preceded by `ASKING` if it was redirected there
+    /// by `ASK`.
+    async fn send(
+        &mut self,
+        node: &Node,
+        cmd: &redis::Cmd,
+        asking: bool,
+    ) -> redis::RedisResult<Value> {
+        self.in_flight = Some(node.clone());
+        let result = match self.connection(node).await {
+            Ok(conn) if asking => match redis::cmd("ASKING").query_async::<_, ()>(conn).await {
+                Ok(()) => conn.req_packed_command(cmd).await,
+                Err(e) => Err(e),
+            },
+            Ok(conn) => conn.req_packed_command(cmd).await,
+            Err(e) => Err(e),
+        };
+        self.settle(node, &result);
+        result
+    }
+
+    /// Records that the reply to a request sent to `node` has been read,
+    /// dropping the connection if it broke.
+    fn settle<T>(&mut self, node: &Node, result: &redis::RedisResult<T>) {
+        self.in_flight = None;
+        if matches!(result, Err(e) if e.is_io_error()) {
+            self.forget(node);
+        }
+    }
+
+    fn drop_in_flight(&mut self) {
+        if let Some(node) = self.in_flight.take() {
+            self.forget(&node);
+        }
+    }
+
+    /// Drops the connection to `node`. The server may have failed over, so
+    /// which server owns each slot is asked again before the next request.
+    fn forget(&mut self, node: &Node) {
+        self.connections.remove(node);
+        self.slots.clear();
+    }
+
+    /// Returns the connection to `node`, connecting if there isn't one yet.
+    async fn connection(&mut self, node: &Node) -> redis::RedisResult<&mut redis::aio::Connection> {
+        match self.connections.entry(node.clone()) {
+            Entry::Occupied(entry) => Ok(entry.into_mut()),
+            Entry::Vacant(entry) => {
+                if !(self.is_allowed)(&url(&self.info, node)) {
+                    return Err(not_allowed(node));
+                }
+                let client = redis::Client::open(node_info(&self.info, node))?;
+                let conn = tokio::time::timeout(self.timeout, client.get_async_connection())
+                    .await
+                    .unwrap_or_else(|_| {
+                        Err(std::io::Error::new(
+                            std::io::ErrorKind::TimedOut,
+                            format!("connection timed out after {:?}", self.timeout),
+                        )
+                        .into())
+                    })?;
+                Ok(entry.insert(conn))
+            }
+        }
+    }
+
+    /// Returns the server to run a command on, asking the cluster which
+    /// server owns each slot first if that isn't known.
+    async fn node_for(&mut self, route: Route) -> redis::RedisResult<Node> {
+        self.refresh_slots().await?;
+        let node = match route {
+            Route::Slot(slot) => self
+                .slots
+                .iter()
+                .find(|slots| (slots.start..=slots.end).contains(&slot))
+                .map(|slots| &slots.node),
+            _ => self
+                .slots
+                .iter()
+                .map(|slots| &slots.node)
+                .find(|node| self.connections.contains_key(node))
+                .or_else(|| self.slots.first().map(|slots| &slots.node)),
+        };
+        node.cloned().ok_or_else(|| {
+            redis::RedisError::from((
+                redis::ErrorKind::ClusterDown,
+                "no cluster server owns the slot",
+            ))
+        })
+    }
+
+    /// Returns every server that owns slots.
+    async fn primaries(&mut self) -> redis::RedisResult<Vec<Node>> {
+        self.refresh_slots().await?;
+        let mut nodes: Vec<Node> = Vec::new();
+        for slots in &self.slots {
+            if !nodes.contains(&slots.node) {
+                nodes.push(slots.node.clone());
+            }
+        }
+        Ok(nodes)
+    }
+
+    /// Asks the cluster which server owns each slot, unless that's known,
+    /// failing if any of them isn't allowed.
+    async fn refresh_slots(&mut self) -> redis::RedisResult<()> {
+        if !self.slots.is_empty() {
+            return Ok(());
+        }
+        let slots = self.fetch_slots().await?;
+        if let Some(node) = self.disallowed(&slots) {
+            return Err(not_allowed(node));
+        }
+        self.slots = slots;
+        Ok(())
+    }
+
+    /// Runs `CLUSTER SLOTS` on the first server that answers, trying those
+    /// already connected to before the seeds.
+    async fn fetch_slots(&mut self) -> redis::RedisResult<Vec<Slots>> {
+        let mut nodes: Vec<Node> = self.connections.keys().cloned().collect();
+        nodes.extend(
+            self.seeds
+                .iter()
+                .filter(|seed| !self.connections.contains_key(seed))
+                .cloned(),
+        );
+        let mut last_error = None;
+        for node in nodes {
+            let reply = match self.connection(&node).await {
+                Ok(conn) => {
+                    redis::cmd("CLUSTER")
+                        .arg("SLOTS")
+                        .query_async::<_, Value>(conn)
+                        .await
+                }
+                Err(e) => Err(e),
+            };
+            match reply.and_then(|reply| parse_slots(&reply, &node.host)) {
+                Ok(slots) => return Ok(slots),
+                Err(e) => {
+                    self.connections.remove(&node);
+                    last_error = Some(e);
+                }
+            }
+        }
+        Err(last_error
+            .unwrap_or_else(|| (redis::ErrorKind::ClusterDown, "no cluster server to ask").into()))
+    }
+
+    /// Returns a server in `slots` that isn't allowed, if there is one.
+    fn disallowed<'a>(&self, slots: &'a [Slots]) -> Option<&'a Node> {
+        slots
+            .iter()
+            .map(|slots| &slots.node)
+            .find(|node| !(self.is_allowed)(&url(&self.info, node)))
+    }
+}
+
+impl ConnectionLike for ClusterConnection {
+    fn req_packed_command<'a>(&'a mut self, cmd: &'a redis::Cmd) -> RedisFuture<'a, Value> {
+        Box::pin(self.request(cmd))
+    }
+
+    fn req_packed_commands<'a>(
+        &'a mut self,
+        cmd: &'a redis::Pipeline,
+        offset: usize,
+        count: usize,
+    ) -> RedisFuture<'a, Vec<Value>> {
+        Box::pin(self.request_pipeline(cmd, offset, count))
+    }
+
+    fn get_db(&self) -> i64 {
+        0
+    }
+}
+
+/// The URL of `node`, for checking against the allowed hosts.
+fn url(info: &ConnectionInfo, node: &Node) -> String {
+    let scheme = match info.addr {
+        ConnectionAddr::TcpTls { .. } => "rediss",
+        _ => "redis",
+    };
+    format!("{scheme}://{node}")
+}
+
+/// Returns `info` with its address replaced by `node`'s.
+fn node_info(info: &ConnectionInfo, node: &Node) -> ConnectionInfo {
+    let (host, port) = (node.host.clone(), node.port);
+    let addr = match info.addr {
+        ConnectionAddr::TcpTls { insecure, .. } => ConnectionAddr::TcpTls {
+            host,
+            port,
+            insecure,
+        },
+        _ => ConnectionAddr::Tcp(host, port),
+    };
+    ConnectionInfo {
+        addr,
+        redis: info.redis.clone(),
+    }
+}
+
+fn not_allowed(node: &Node) -> redis::RedisError {
+    redis::RedisError::from((
+        redis::ErrorKind::ClientError,
+        "cluster server is not allowed",
+        node.to_string(),
+    ))
+}
+
+/// Parses a reply to `CLUSTER SLOTS` from a server on `host`, which servers
+/// that don't know their own address are listed with.
+fn parse_slots(reply: &Value, host: &str) -> redis::RedisResult<Vec<Slots>> {
+    let parse = |range: &Value| {
+        let Value::Bulk(range) = range else {
+            return None;
+        };
+        let [Value::Int(start), Value::Int(end), Value::Bulk(primary), ..] = &range[..] else {
+            return None;
+        };
+        let [Value::Data(node_host), Value::Int(port), ..] = &primary[..] else {
+            return None;
+        };
+        let node_host = match std::str::from_utf8(node_host).ok()? {
+            "" => host,
+            node_host => node_host,
+        };
+        Some(Slots {
+            start: u16::try_from(*start).ok()?,
+            end: u16::try_from(*end).ok()?,
+            node: Node {
+                host: node_host.to_owned(),
+                port: u16::try_from(*port).ok()?,
+            },
+        })
+    };
+    let ranges: Option<Vec<Slots>> = match reply {
+        Value::Bulk(ranges) => ranges.iter().map(parse).collect(),
+        _ => None,
+    };
+    ranges.ok_or_else(|| {
+        (
+            redis::ErrorKind::TypeError,
+            "unexpected reply to CLUSTER SLOTS",
+        )
+            .into()
+    })
+}
+
+/// Where a command is run.
+#[derive(Debug, PartialEq)]
+enum Route {
+    /// On the server that owns the slot of the command's key.
+    Slot(u16),
+    /// On any server, as the command has no key.
+    Any,
+    /// On every server that owns slots, as the command changes each server's
+    /// state rather than a key's, such as `SCRIPT LOAD`.
+    AllPrimaries,
+}
+
+fn route(cmd: &redis::Cmd) -> Route {
+    let args: Vec<&[u8]> = cmd
+        .args_iter()
+        .filter_map(|arg| match arg {
+            Arg::Simple(arg) => Some(arg),
+            Arg::Cursor => None,
+        })
+        .collect();
+    let Some(name) = args.first() else {
+        return Route::Any;
+    };
+    let key = match &name.to_ascii_uppercase()[..] {
+        b"SCRIPT" | b"FLUSHALL" | b"FLUSHDB" => return Route::AllPrimaries,
+        b"PING" | b"ECHO" | b"INFO" | b"TIME" | b"DBSIZE" | b"KEYS" | b"SCAN" | b"CONFIG"
+        | b"CLIENT" | b"CLUSTER" | b"PUBLISH" | b"SELECT" => None,
+        b"EVAL" | b"EVALSHA" => match args.get(2) {
+            Some(&b"0") => None,
+            _ => args.get(3),
+        },
+        b"BITOP" | b"XGROUP" | b"XINFO" => args.get(2),
+        b"XREAD" | b"XREADGROUP" => args
+            .iter()
+            .position(|arg| arg.eq_ignore_ascii_case(b"STREAMS"))
+            .and_then(|streams| args.get(streams + 1)),
+        _ => args.get(1),
+    };
+    key.map_or(Route::Any, |key| Route::Slot(slot(key)))
+}
+
+/// Returns the hash slot of `key`. Only the part of the key in braces counts,
+/// if there is one, so that related keys can be kept on the same server.
+fn slot(key: &[u8]) -> u16 {
+    let tag = key.iter().position(|&b| b == b'{').and_then(|open| {
+        let tag = &key[open + 1..];
+        let close = tag.iter().position(|&b| b == b'}')?;
+        (close > 0).then(|| &tag[..close])
+    });
+    crc16(tag.unwrap_or(key)) % SLOT_COUNT
+}
+
+/// The CRC-16/XMODEM checksum that the cluster hashes keys with.
+fn crc16(data: &[u8]) -> u16 {
+    data.iter().fold(0, |crc, &byte| {
+        (0..8).fold(crc ^ (u16::from(byte) << 8), |crc, _| {
+            if crc & 0x8000 == 0 {
+                crc << 1
+            } else {
+                (crc << 1) ^ 0x1021
+            }
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cluster_addresses_are_recognised() {
+        assert_eq!(None, seeds("redis://a:6379"));
+        assert_eq!(
+            Some(vec!["redis://a:6379", "redis://b:6379"]),
+            seeds("redis://a:6379, redis://b:6379")
+        );
+        assert_eq!(
+            Some(vec!["redis://a:6379"]),
+            seeds("redis://a:6379#cluster")
+        );
+        assert_eq!(None, seeds("redis://:pass,word@a:6379"));
+        assert_eq!(
+            Some(vec![
+                "redis://:pass,word@a:6379",
+                "redis://:pass,word@b:6379"
+            ]),
+            seeds("redis://:pass,word@a:6379,redis://:pass,word@b:6379")
+        );
+    }
+
+    #[test]
+    fn keys_are_hashed_to_slots() {
+        assert_eq!(0x31c3, crc16(b"123456789"));
+        assert_eq!(12182, slot(b"foo"));
+        assert_eq!(slot(b"user1000"), slot(b"{user1000}.following"));
+        assert_eq!(slot(b"user1000"), slot(b"x{user1000}{y}"));
+        assert_eq!(crc16(b"{}user1000") % SLOT_COUNT, slot(b"{}user1000"));
+    }
+
+    #[test]
+    fn commands_are_routed_by_key() {
+        assert_eq!(Route::Slot(slot(b"k")), route(redis::cmd("GET").arg("k")));
+        assert_eq!(Route::Any, route(&redis::cmd("PING")));
+        assert_eq!(
+            Route::AllPrimaries,
+            route(redis::cmd("SCRIPT").arg("LOAD").arg("return 1"))
+        );
+        assert_eq!(Route::Any, route(redis::cmd("EVAL").arg("return 1").arg(0)));
+        assert_eq!(
+            Route::Slot(slot(b"k")),
+            route(redis::cmd("EVAL").arg("return 1").arg(1).arg("k"))
+        );
+        assert_eq!(
+            Route::Slot(slot(b"s")),
+            route(
+                redis::cmd("XREAD")
+                    .arg("COUNT")
+                    .arg(1)
+                    .arg("STREAMS")
+                    .arg("s")
+                    .arg(0)
+            )
+        );
+    }
+
+    /// Serves one connection on a port of its own, answering each request
+    /// with the next of the replies that `replies` returns for that port.
+    async fn serve(replies: impl FnOnce(u16) -> Vec<String>) -> (ConnectionInfo, u16) {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let replies = replies(port);
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = [0; 256];
+            for reply in replies {
+                let len = socket.read(&mut buf).await.unwrap();
+                assert!(len > 0, "the client closed the connection");
+                socket.write_all(reply.as_bytes()).await.unwrap();
+            }
+        });
+        let info = format!("redis://127.0.0.1:{port}").parse().unwrap();
+        (info, port)
+    }
+
+    fn allowing(port: u16) -> AllowedHosts {
+        Box::new(move |url| url == format!("redis://127.0.0.1:{port}"))
+    }
+
+    fn node_reply(host: &str, port: u16) -> String {
+        format!("*2\r\n${}\r\n{host}\r\n:{port}\r\n", host.len())
+    }
+
+    #[tokio::test]
+    async fn servers_that_are_not_allowed_are_refused() {
+        let (info, port) = serve(|port| {
+            vec![format!(
+                "*2\r\n*3\r\n:0\r\n:8191\r\n{}*3\r\n:8192\r\n:16383\r\n{}",
+                node_reply("127.0.0.1", port),
+                node_reply("10.0.0.1", 6379)
+            )]
+        })
+        .await;
+
+        let result = connect(vec![info], allowing(port), Duration::from_secs(5)).await;
+        assert!(matches!(result, Err(Error::InvalidAddress)));
+    }
+
+    #[tokio::test]
+    async fn redirects_to_servers_that_are_not_allowed_are_refused() {
+        // The server doesn't know its own address, so lists itself without one.
+        let (info, port) = serve(|port| {
+            vec![
+                format!("*1\r\n*3\r\n:0\r\n:16383\r\n{}", node_reply("", port)),
+                "-MOVED 3999 10.0.0.1:6379\r\n".into(),
+            ]
+        })
+        .await;
+
+        let mut cluster = connect(vec![info], allowing(port), Duration::from_secs(5))
+            .await
+            .unwrap();
+        let err = cluster
+            .req_packed_command(redis::cmd("GET").arg("k"))
+            .await
+            .unwrap_err();
+        assert_eq!(redis::ErrorKind::ClientError, err.kind());
+        assert_eq!(Some("10.0.0.1:6379"), err.detail());
+    }
+}
@@ -1,11 +1,23 @@
+use std::time::Duration;
+
 use anyhow::Context;
 use spin_app::DynamicHostComponent;
 use spin_core::HostComponent;
 
-use crate::OutboundRedis;
+use crate::{OutboundRedis, RedisCredentials};
 
 pub struct OutboundRedisComponent {
     pub resolver: spin_expressions::SharedPreparedResolver,
+    /// Used to authenticate connections to servers whose address doesn't
+    /// include credentials.
+    pub credentials: Option<RedisCredentials>,
+    /// How long to wait for a connection to a server to be established.
+    pub connect_timeout: Duration,
+    /// How long to wait for the reply to each command, if limited.
+    pub command_timeout: Option<Duration>,
+    /// The database to select on each connection, in place of the one its
+    /// address names.
+    pub db: Option<i64>,
 }
 
 impl HostComponent for OutboundRedisComponent {
@@ -15,11 +27,18 @@ impl HostComponent for OutboundRedisComponent {
         get: impl Fn(&mut spin_core::Data<T>) -> &mut Self::Data + Send + Sync + Copy + 'static,
     ) -> anyhow::Result<()> {
         spin_world::v1::redis::add_to_linker(linker, get)?;
-        spin_world::v2::redis::add_to_linker(linker, get)
+        spin_world::v2::redis::add_to_linker(linker, get)?;
+        spin_world::v2_1::redis::add_to_linker(linker, get)
     }
 
     fn build_data(&self) -> Self::Data {
-        Default::default()
+        OutboundRedis {
+            credentials: self.credentials.clone(),
+            connect_timeout: self.connect_timeout,
+            command_timeout: self.command_timeout,
+            db: self.db,
+            ..Default::default()
+        }
     }
 }
 
@@ -0,0 +1,332 @@
+//! Replies to general-purpose commands, as lists of `redis-result`.
+//!
+//! The client speaks RESP2, in which doubles, booleans and maps arrive as the
+//! bulk strings, integers and arrays that represent them. Their types are
+//! recovered from the command that was sent, so that, for example, the score
+//! returned by `ZSCORE` is given as a double rather than as its decimal text.
+
+use redis::{ErrorKind, RedisError, Value};
+use spin_world::v2_1::redis::{RedisParameter, RedisResult};
+
+/// What a command's reply represents, beyond what RESP2 says about it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Shape {
+    /// The reply is given as it was received.
+    Plain,
+    /// A double, or nil.
+    Double,
+    /// A boolean, sent as 0 or 1.
+    Boolean,
+    /// An array of doubles or nils.
+    Doubles,
+    /// An array of booleans.
+    Booleans,
+    /// An array of alternating keys and values.
+    Map,
+    /// An array of alternating sorted set members and their scores.
+    Scored,
+}
+
+impl Shape {
+    /// The shape of the reply to `command` with `arguments`.
+    pub(crate) fn of(command: &str, arguments: &[RedisParameter]) -> Self {
+        match command.to_ascii_uppercase().as_str() {
+            "ZSCORE" | "ZINCRBY" | "INCRBYFLOAT" | "HINCRBYFLOAT" => Shape::Double,
+            // ZADD key [NX | XX] [GT | LT] [CH] [INCR] score member
+            "ZADD"
+                if arguments
+                    .iter()
+                    .skip(1)
+                    .take_while(|a| {
+                        ["NX", "XX", "GT", "LT", "CH", "INCR"]
+                            .iter()
+                            .any(|o| is(a, o))
+                    })
+                    .any(|a| is(a, "INCR")) =>
+            {
+                Shape::Double
+            }
+            "ZMSCORE" => Shape::Doubles,
+            "SISMEMBER" | "HEXISTS" | "SETNX" | "HSETNX" | "MSETNX" | "RENAMENX" | "SMOVE"
+            | "PERSIST" | "EXPIRE" | "PEXPIRE" | "EXPIREAT" | "PEXPIREAT" | "COPY" | "MOVE" => {
+                Shape::Boolean
+            }
+            "SMISMEMBER" => Shape::Booleans,
+            "HGETALL" => Shape::Map,
+            "CONFIG" if arguments.first().is_some_and(|a| is(a, "GET")) => Shape::Map,
+            "ZPOPMIN" | "ZPOPMAX" => Shape::Scored,
+            "ZRANGE" | "ZREVRANGE" | "ZRANGEBYSCORE" | "ZREVRANGEBYSCORE" | "ZUNION" | "ZINTER"
+            | "ZDIFF" | "ZRANDMEMBER"
+                if arguments.last().is_some_and(|a| is(a, "WITHSCORES")) =>
+            {
+                Shape::Scored
+            }
+            _ => Shape::Plain,
+        }
+    }
+}
+
+/// Whether `argument` is the keyword `word`, in any case.
+fn is(argument: &RedisParameter, word: &str) -> bool {
+    matches!(argument, RedisParameter::Binary(b) if b.eq_ignore_ascii_case(word.as_bytes()))
+}
+
+/// The results of `value`, a reply of the given `shape`.
+///
+/// An array reply is given as its elements, and any other reply alone.
+pub(crate) fn results(shape: Shape, value: &Value) -> redis::RedisResult<Vec<RedisResult>> {
+    let mut results = Vec::new();
+    match (shape, value) {
+        (Shape::Plain, Value::Bulk(values)) => {
+            for value in values {
+                append(&mut results, value)?;
+            }
+        }
+        (Shape::Plain, value) => append(&mut results, value)?,
+        (Shape::Double, value) => results.push(double(value)?),
+        (Shape::Boolean, value) => results.push(boolean(value)?),
+        (Shape::Doubles, Value::Bulk(values)) => {
+            for value in values {
+                results.push(double(value)?);
+            }
+        }
+        (Shape::Booleans, Value::Bulk(values)) => {
+            for value in values {
+                results.push(boolean(value)?);
+            }
+        }
+        (Shape::Map, Value::Bulk(values)) => {
+            let pairs = pairs(values)?;
+            results.push(RedisResult::Map(count(pairs.len())?));
+            for [key, value] in pairs {
+                append(&mut results, key)?;
+                append(&mut results, value)?;
+            }
+        }
+        (Shape::Scored, Value::Bulk(values)) => {
+            for [member, score] in pairs(values)? {
+                append(&mut results, member)?;
+                results.push(double(score)?);
+            }
+        }
+        (shape, value) => {
+            return Err(type_error(format!(
+                "expected an array for a {shape:?} reply, got {value:?}"
+            )))
+        }
+    }
+    Ok(results)
+}
+
+/// Appends `value` to `results`, preceded by a header if it's an array.
+fn append(results: &mut Vec<RedisResult>, value: &Value) -> redis::RedisResult<()> {
+    match value {
+        Value::Nil => results.push(RedisResult::Nil),
+        Value::Int(v) => results.push(RedisResult::Int64(*v)),
+        Value::Data(bytes) => results.push(RedisResult::Binary(bytes.to_owned())),
+        Value::Status(message) => results.push(RedisResult::Status(message.to_owned())),
+        Value::Okay => results.push(RedisResult::Status("OK".into())),
+        Value::Bulk(values) => {
+            results.push(RedisResult::Array(count(values.len())?));
+            for value in values {
+                append(results, value)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn double(value: &Value) -> redis::RedisResult<RedisResult> {
+    match value {
+        Value::Nil => Ok(RedisResult::Nil),
+        Value::Int(v) => Ok(RedisResult::Double(*v as f64)),
+        Value::Data(bytes) => std::str::from_utf8(bytes)
+            .ok()
+            .and_then(|text| text.parse().ok())
+            .map(RedisResult::Double)
+            .ok_or_else(|| type_error(format!("expected a double, got {value:?}"))),
+        _ => Err(type_error(format!("expected a double, got {value:?}"))),
+    }
+}
+
+fn boolean(value: &Value) -> redis::RedisResult<RedisResult> {
+    match value {
+        Value::Nil => Ok(RedisResult::Nil),
+        Value::Int(v) => Ok(RedisResult::Boolean(*v != 0)),
+        _ => Err(type_error(format!("expected a boolean, got {value:?}"))),
+    }
+}
+
+/// Splits `values` into pairs, such as the fields and values of a hash.
+fn pairs(values: &[Value]) -> redis::RedisResult<Vec<[&Value; 2]>> {
+    let chunks = values.chunks_exact(2);
+    if !chunks.remainder().is_empty() {
+        return Err(type_error(format!(
+            "expected pairs, got {} values",
+            values.len()
+        )));
+    }
+    Ok(chunks.map(|p| [&p[0], &p[1]]).collect())
+}
+
+fn count(len: usize) -> redis::RedisResult<u32> {
+    u32::try_from(len).map_err(|_| type_error(format!("{len} values are too many to count")))
+}
+
+fn type_error(detail: String) -> RedisError {
+    RedisError::from((ErrorKind::TypeError, "unexpected reply", detail))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn binary(s: &str) -> RedisParameter {
+        RedisParameter::Binary(s.as_bytes().to_vec())
+    }
+
+    fn data(s: &str) -> Value {
+        Value::Data(s.as_bytes().to_vec())
+    }
+
+    #[test]
+    fn shapes_follow_the_command() {
+        assert_eq!(
+            Shape::Double,
+            Shape::of("zscore", &[binary("z"), binary("m")])
+        );
+        assert_eq!(
+            Shape::Double,
+            Shape::of(
+                "ZADD",
+                &[
+                    binary("z"),
+                    binary("XX"),
+                    binary("INCR"),
+                    RedisParameter::Int64(1),
+                    binary("m")
+                ]
+            )
+        );
+        assert_eq!(
+            Shape::Plain,
+            Shape::of(
+                "ZADD",
+                &[binary("z"), RedisParameter::Int64(1), binary("incr")]
+            )
+        );
+        assert_eq!(
+            Shape::Boolean,
+            Shape::of("SISMEMBER", &[binary("s"), binary("m")])
+        );
+        assert_eq!(
+            Shape::Map,
+            Shape::of("CONFIG", &[binary("get"), binary("*")])
+        );
+        assert_eq!(
+            Shape::Plain,
+            Shape::of("CONFIG", &[binary("SET"), binary("k"), binary("v")])
+        );
+        assert_eq!(
+            Shape::Scored,
+            Shape::of(
+                "ZRANGE",
+                &[
+                    binary("z"),
+                    RedisParameter::Int64(0),
+                    RedisParameter::Int64(-1),
+                    binary("WITHSCORES")
+                ]
+            )
+        );
+        assert_eq!(
+            Shape::Plain,
+            Shape::of(
+                "ZRANGE",
+                &[
+                    binary("z"),
+                    RedisParameter::Int64(0),
+                    RedisParameter::Int64(-1)
+                ]
+            )
+        );
+        assert_eq!(Shape::Plain, Shape::of("GET", &[binary("k")]));
+    }
+
+    #[test]
+    fn nested_arrays_are_given_with_headers() {
+        let value = Value::Bulk(vec![
+            data("17"),
+            Value::Bulk(vec![data("a"), Value::Nil]),
+            Value::Okay,
+        ]);
+        let results = results(Shape::Plain, &value).unwrap();
+        assert!(matches!(
+            &results[..],
+            [
+                RedisResult::Binary(cursor),
+                RedisResult::Array(2),
+                RedisResult::Binary(a),
+                RedisResult::Nil,
+                RedisResult::Status(ok),
+            ] if cursor == b"17" && a == b"a" && ok == "OK"
+        ));
+    }
+
+    #[test]
+    fn typed_replies() {
+        assert!(matches!(
+            results(Shape::Double, &data("1.5")).unwrap()[..],
+            [RedisResult::Double(v)] if v == 1.5
+        ));
+        assert!(matches!(
+            results(Shape::Double, &data("-inf")).unwrap()[..],
+            [RedisResult::Double(v)] if v == f64::NEG_INFINITY
+        ));
+        assert!(matches!(
+            results(Shape::Double, &Value::Nil).unwrap()[..],
+            [RedisResult::Nil]
+        ));
+        assert!(matches!(
+            results(
+                Shape::Booleans,
+                &Value::Bulk(vec![Value::Int(1), Value::Int(0)])
+            )
+            .unwrap()[..],
+            [RedisResult::Boolean(true), RedisResult::Boolean(false)]
+        ));
+    }
+
+    #[test]
+    fn maps_and_scores_are_paired() {
+        let value = Value::Bulk(vec![data("f"), data("v")]);
+        assert!(matches!(
+            &results(Shape::Map, &value).unwrap()[..],
+            [RedisResult::Map(1), RedisResult::Binary(f), RedisResult::Binary(v)]
+                if f == b"f" && v == b"v"
+        ));
+
+        let value = Value::Bulk(vec![data("m"), data("2")]);
+        assert!(matches!(
+            &results(Shape::Scored, &value).unwrap()[..],
+            [RedisResult::Binary(m), RedisResult::Double(s)] if m == b"m" && *s == 2.0
+        ));
+    }
+
+    #[test]
+    fn malformed_replies_are_type_errors() {
+        let odd = Value::Bulk(vec![data("f")]);
+        assert_eq!(
+            ErrorKind::TypeError,
+            results(Shape::Map, &odd).unwrap_err().kind()
+        );
+        assert_eq!(
+            ErrorKind::TypeError,
+            results(Shape::Double, &data("many")).unwrap_err().kind()
+        );
+        assert_eq!(
+            ErrorKind::TypeError,
+            results(Shape::Doubles, &Value::Int(1)).unwrap_err().kind()
+        );
+    }
+}
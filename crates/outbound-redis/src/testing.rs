@@ -0,0 +1,188 @@
+//! Scripted stand-ins for Redis connections, so that guests using outbound
+//! Redis can be tested without a live server.
+//!
+//! ```
+//! use outbound_redis::testing::{MockCmd, MockConnection};
+//! use outbound_redis::OutboundRedis;
+//! use redis::Value;
+//!
+//! let connection = MockConnection::new([
+//!     MockCmd::new(redis::cmd("GET").arg("greeting"), Ok(Value::Data(b"hello".to_vec()))),
+//! ]);
+//! let redis = OutboundRedis::mock_builder()
+//!     .connection("redis://localhost", connection.clone())
+//!     .build();
+//! // ... run the guest with `redis` as its host data ...
+//! assert!(connection.is_exhausted());
+//! ```
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+
+use redis::{aio::ConnectionLike, RedisFuture, RedisResult, Value};
+
+use crate::OutboundRedis;
+
+/// A command a [`MockConnection`] expects to receive, and its reply.
+pub struct MockCmd {
+    packed: Vec<u8>,
+    response: RedisResult<Value>,
+}
+
+impl MockCmd {
+    /// Expects `cmd`, replying with `response`.
+    pub fn new(cmd: &redis::Cmd, response: RedisResult<Value>) -> Self {
+        Self {
+            packed: cmd.get_packed_command(),
+            response,
+        }
+    }
+}
+
+/// A connection that replies to a script of commands, in order. Receiving a
+/// command other than the next one in the script is reported as an error.
+///
+/// Clones share the script, so a test can keep a clone to check that every
+/// command was received.
+#[derive(Clone, Default)]
+pub struct MockConnection {
+    commands: Arc<Mutex<VecDeque<MockCmd>>>,
+}
+
+impl MockConnection {
+    pub fn new(commands: impl IntoIterator<Item = MockCmd>) -> Self {
+        Self {
+            commands: Arc::new(Mutex::new(commands.into_iter().collect())),
+        }
+    }
+
+    /// Returns whether every command in the script has been received.
+    pub fn is_exhausted(&self) -> bool {
+        self.commands.lock().unwrap().is_empty()
+    }
+
+    fn reply(&self, packed: &[u8]) -> RedisResult<Value> {
+        let unexpected = || {
+            let received = String::from_utf8_lossy(packed).into_owned();
+            redis::RedisError::from((
+                redis::ErrorKind::ClientError,
+                "unexpected command",
+                received,
+            ))
+        };
+        let mut commands = self.commands.lock().unwrap();
+        match commands.front() {
+            Some(next) if next.packed == packed => commands.pop_front().unwrap().response,
+            _ => Err(unexpected()),
+        }
+    }
+}
+
+impl ConnectionLike for MockConnection {
+    fn req_packed_command<'a>(&'a mut self, cmd: &'a redis::Cmd) -> RedisFuture<'a, Value> {
+        let reply = self.reply(&cmd.get_packed_command());
+        Box::pin(async move { reply })
+    }
+
+    fn req_packed_commands<'a>(
+        &'a mut self,
+        cmd: &'a redis::Pipeline,
+        offset: usize,
+        count: usize,
+    ) -> RedisFuture<'a, Vec<Value>> {
+        let replies = cmd
+            .cmd_iter()
+            .map(|cmd| self.reply(&cmd.get_packed_command()))
+            .collect::<RedisResult<Vec<_>>>()
+            .map(|replies| {
+                // A transaction is answered with a single reply, to `EXEC`,
+                // following those to `MULTI` and each queued command.
+                if offset == replies.len() + 1 && count == 1 {
+                    vec![Value::Bulk(replies)]
+                } else {
+                    replies.into_iter().skip(offset).take(count).collect()
+                }
+            });
+        Box::pin(async move { replies })
+    }
+
+    fn get_db(&self) -> i64 {
+        0
+    }
+}
+
+/// Builds an [`OutboundRedis`] whose connections are [`MockConnection`]s.
+///
+/// Every address is allowed, and opening an address without a mock connection
+/// fails rather than connecting to a server.
+#[derive(Default)]
+pub struct MockOutboundRedisBuilder {
+    connections: HashMap<String, MockConnection>,
+}
+
+impl MockOutboundRedisBuilder {
+    /// Uses `connection` for connections opened to `address`.
+    pub fn connection(mut self, address: impl Into<String>, connection: MockConnection) -> Self {
+        self.connections.insert(address.into(), connection);
+        self
+    }
+
+    pub fn build(self) -> OutboundRedis {
+        OutboundRedis {
+            allowed_hosts: spin_outbound_networking::AllowedHostsConfig::All,
+            mock_connections: Some(self.connections),
+            ..Default::default()
+        }
+    }
+}
+
+impl OutboundRedis {
+    /// Returns a builder for an instance backed by scripted connections.
+    pub fn mock_builder() -> MockOutboundRedisBuilder {
+        Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use spin_core::wasmtime::component::Resource;
+    use spin_world::v2_1::redis::{Error, HostConnection};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn commands_are_answered_from_the_script() {
+        let connection = MockConnection::new([
+            MockCmd::new(redis::cmd("SET").arg("k").arg(b"v"), Ok(Value::Okay)),
+            MockCmd::new(redis::cmd("GET").arg("k"), Ok(Value::Data(b"v".to_vec()))),
+        ]);
+        let mut redis = OutboundRedis::mock_builder()
+            .connection("redis://localhost", connection.clone())
+            .build();
+
+        let conn = redis.open("redis://localhost".into()).await.unwrap();
+        let rep = conn.rep();
+        redis
+            .set(Resource::new_borrow(rep), "k".into(), b"v".to_vec())
+            .await
+            .unwrap();
+        let value = redis
+            .get(Resource::new_borrow(rep), "k".into())
+            .await
+            .unwrap();
+        assert_eq!(Some(b"v".to_vec()), value);
+        assert!(connection.is_exhausted());
+
+        let err = redis
+            .incr(Resource::new_borrow(rep), "k".into())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::Other(m) if m.contains("unexpected command")));
+    }
+
+    #[tokio::test]
+    async fn unmocked_addresses_are_not_opened() {
+        let mut redis = OutboundRedis::mock_builder().build();
+        assert!(redis.open("redis://localhost".into()).await.is_err());
+    }
+}
@@ -19,6 +19,29 @@ pub enum Error {
     Other(#[from] anyhow::Error),
 }
 
+/// Error for when a plugin doesn't support this version of Spin, or this
+/// platform.
+#[derive(Debug)]
+pub struct IncompatibleError {
+    message: String,
+}
+
+impl IncompatibleError {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+        }
+    }
+}
+
+impl std::fmt::Display for IncompatibleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for IncompatibleError {}
+
 /// Contains error details for when a plugin resource cannot be found at expected location
 #[derive(Debug)]
 pub struct NotFoundError {
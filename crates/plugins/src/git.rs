@@ -4,17 +4,17 @@ use std::path::{Path, PathBuf};
 use tokio::process::Command;
 use url::Url;
 
-const DEFAULT_BRANCH: &str = "main";
-
 /// Enables cloning and fetching the latest of a git repository to a local
 /// directory.
 pub struct GitSource {
     /// Address to remote git repository.
     source_url: Url,
-    /// Branch to clone/fetch.
-    branch: String,
+    /// Branch or tag to clone/fetch, or the remote's default branch if none.
+    branch: Option<String>,
     /// Destination to clone repository into.
     git_root: PathBuf,
+    /// Proxy to connect through, rather than any configured for Git.
+    proxy: Option<Url>,
 }
 
 impl GitSource {
@@ -22,22 +22,33 @@ impl GitSource {
     pub fn new(source_url: &Url, branch: Option<String>, git_root: impl AsRef<Path>) -> GitSource {
         Self {
             source_url: source_url.clone(),
-            branch: branch.unwrap_or_else(|| DEFAULT_BRANCH.to_owned()),
+            branch,
             git_root: git_root.as_ref().to_owned(),
+            proxy: None,
+        }
+    }
+
+    /// Connects to the remote repository through `proxy`, if given.
+    pub fn with_proxy(self, proxy: Option<Url>) -> Self {
+        Self { proxy, ..self }
+    }
+
+    fn git(&self) -> Command {
+        let mut git = Command::new("git");
+        if let Some(proxy) = &self.proxy {
+            git.arg("-c").arg(format!("http.proxy={proxy}"));
         }
+        git
     }
 
     /// Clones a contents of a git repository to a local directory
     pub async fn clone_repo(&self) -> Result<()> {
-        let mut git = Command::new("git");
-        git.args([
-            "clone",
-            self.source_url.as_ref(),
-            "--branch",
-            &self.branch,
-            "--single-branch",
-        ])
-        .arg(&self.git_root);
+        let mut git = self.git();
+        git.args(["clone", self.source_url.as_ref(), "--single-branch"]);
+        if let Some(branch) = &self.branch {
+            git.args(["--branch", branch]);
+        }
+        git.arg(&self.git_root);
         let clone_result = git.output().await.understand_git_result();
         if let Err(e) = clone_result {
             anyhow::bail!("Error cloning Git repo {}: {}", self.source_url, e)
@@ -45,10 +56,13 @@ impl GitSource {
         Ok(())
     }
 
-    /// Fetches the latest changes from the source repository
+    /// Fetches the latest changes from the source repository, fast-forwarding
+    /// the local copy rather than cloning it again.
     pub async fn pull(&self) -> Result<()> {
-        let mut git = Command::new("git");
-        git.arg("-C").arg(&self.git_root).arg("pull");
+        let mut git = self.git();
+        git.arg("-C")
+            .arg(&self.git_root)
+            .args(["pull", "--ff-only"]);
         let pull_result = git.output().await.understand_git_result();
         if let Err(e) = pull_result {
             anyhow::bail!(
@@ -61,6 +75,27 @@ impl GitSource {
     }
 }
 
+/// The URL of the repository that the local copy at `git_root` was cloned
+/// from.
+pub(crate) async fn origin_url(git_root: &Path) -> Result<Url> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(git_root)
+        .args(["remote", "get-url", "origin"])
+        .output()
+        .await
+        .understand_git_result();
+    let stdout = match output {
+        Ok(stdout) => stdout,
+        Err(e) => anyhow::bail!(
+            "Error reading the origin of Git repo at {}: {}",
+            git_root.display(),
+            e
+        ),
+    };
+    Ok(Url::parse(String::from_utf8_lossy(&stdout).trim())?)
+}
+
 // TODO: the following and templates/git.rs are duplicates
 
 pub(crate) enum GitError {
@@ -1,10 +1,16 @@
 pub mod badger;
 pub mod error;
 mod git;
+pub mod lockfile;
 pub mod lookup;
 pub mod manager;
 pub mod manifest;
+pub mod pins;
+mod progress;
+mod signature;
 mod store;
+#[cfg(test)]
+mod testing;
 pub use store::PluginStore;
 
 /// List of Spin internal subcommands
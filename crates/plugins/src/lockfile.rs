@@ -0,0 +1,172 @@
+//! Lockfiles pin the plugins a project uses, so that everyone working on it
+//! installs the same versions.
+
+use std::{collections::BTreeMap, path::Path};
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::{lookup::PluginLookup, manager::ManifestLocation, manifest::PluginManifest};
+
+/// Name of the lockfile, which lives in the project directory.
+pub const LOCKFILE_NAME: &str = "spin-plugins.lock";
+
+/// The plugins pinned by a lockfile, by name.
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+pub struct PluginLockfile {
+    plugins: BTreeMap<String, LockedPlugin>,
+}
+
+/// A plugin version pinned by a lockfile.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct LockedPlugin {
+    version: String,
+    /// Where the manifest was downloaded from, if not the plugins repository.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    manifest_url: Option<Url>,
+    /// Checksums of the plugin's packages, by `os/arch`.
+    sha256: BTreeMap<String, String>,
+}
+
+impl PluginLockfile {
+    /// Reads the lockfile at `path`.
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        serde_json::from_str(&text).with_context(|| format!("Invalid lockfile {}", path.display()))
+    }
+
+    /// Reads the lockfile at `path`, or returns an empty one if there is none.
+    pub fn load_or_default(path: &Path) -> Result<Self> {
+        if path.exists() {
+            Self::load(path)
+        } else {
+            Ok(Self::default())
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let text = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, text).with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// Pins the plugin described by `manifest`, replacing any version of it
+    /// already pinned.
+    pub fn lock(&mut self, manifest: &PluginManifest, source: &ManifestLocation) -> Result<()> {
+        let manifest_url = match source {
+            ManifestLocation::PluginsRepository(_) => None,
+            ManifestLocation::Remote(url) => Some(url.clone()),
+            ManifestLocation::Local(path) => bail!(
+                "Plugin '{}' was installed from the local file {}, which can't be locked",
+                manifest.name(),
+                path.display()
+            ),
+            ManifestLocation::Git { url, .. } => bail!(
+                "Plugin '{}' was installed from the Git repository {url}, which can't be locked",
+                manifest.name()
+            ),
+        };
+        self.plugins.insert(
+            manifest.name(),
+            LockedPlugin {
+                version: manifest.version().to_owned(),
+                manifest_url,
+                sha256: checksums(manifest),
+            },
+        );
+        Ok(())
+    }
+
+    pub fn plugins(&self) -> impl Iterator<Item = (&str, &LockedPlugin)> {
+        self.plugins
+            .iter()
+            .map(|(name, locked)| (name.as_str(), locked))
+    }
+}
+
+impl LockedPlugin {
+    /// Where to find the manifest of the plugin named `name` at the pinned version.
+    pub fn manifest_location(&self, name: &str) -> Result<ManifestLocation> {
+        match &self.manifest_url {
+            Some(url) => Ok(ManifestLocation::Remote(url.clone())),
+            None => {
+                let version = semver::Version::parse(&self.version)
+                    .with_context(|| format!("Invalid version {} for {name}", self.version))?;
+                Ok(ManifestLocation::PluginsRepository(PluginLookup::new(
+                    name,
+                    Some(version),
+                )))
+            }
+        }
+    }
+
+    /// Checks that `manifest` is the one that was pinned, so that a changed
+    /// manifest or package isn't installed in its place.
+    pub fn verify(&self, manifest: &PluginManifest) -> Result<()> {
+        if manifest.version() != self.version {
+            bail!(
+                "expected version {}, found {}",
+                self.version,
+                manifest.version()
+            );
+        }
+        if checksums(manifest) != self.sha256 {
+            bail!("package checksums differ from those locked");
+        }
+        Ok(())
+    }
+}
+
+fn checksums(manifest: &PluginManifest) -> BTreeMap<String, String> {
+    manifest
+        .packages
+        .iter()
+        .map(|p| (format!("{}/{}", p.os(), p.arch()), p.sha256.clone()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+
+    fn manifest(version: &str, sha256: &str) -> PluginManifest {
+        let url = "https://example.com/example.tar.gz";
+        testing::manifest(
+            version,
+            serde_json::json!([testing::package_json("linux", "amd64", url, sha256)]),
+        )
+    }
+
+    #[test]
+    fn locked_plugins_round_trip() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let path = temp_dir.path().join(LOCKFILE_NAME);
+        let mut lockfile = PluginLockfile::load_or_default(&path)?;
+        lockfile.lock(
+            &manifest("1.0.0", "abc"),
+            &ManifestLocation::PluginsRepository(PluginLookup::new("example", None)),
+        )?;
+        lockfile.save(&path)?;
+
+        assert_eq!(lockfile, PluginLockfile::load(&path)?);
+        Ok(())
+    }
+
+    #[test]
+    fn only_the_locked_manifest_verifies() -> Result<()> {
+        let mut lockfile = PluginLockfile::default();
+        lockfile.lock(
+            &manifest("1.0.0", "abc"),
+            &ManifestLocation::PluginsRepository(PluginLookup::new("example", None)),
+        )?;
+        let (_, locked) = lockfile.plugins().next().unwrap();
+
+        locked.verify(&manifest("1.0.0", "abc"))?;
+        assert!(locked.verify(&manifest("1.0.1", "abc")).is_err());
+        assert!(locked.verify(&manifest("1.0.0", "def")).is_err());
+        Ok(())
+    }
+}
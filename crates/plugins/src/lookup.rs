@@ -1,4 +1,10 @@
-use crate::{error::*, git::GitSource, manifest::PluginManifest, store::manifest_file_name};
+use crate::{
+    error::*,
+    git::{origin_url, GitSource},
+    manifest::PluginManifest,
+    store::{manifest_file_name, PluginStore},
+};
+use anyhow::Context;
 use semver::Version;
 use std::{
     fs::File,
@@ -43,7 +49,7 @@ impl PluginLookup {
             return Ok(exact);
         }
 
-        let store = crate::store::PluginStore::new(plugins_dir.to_owned());
+        let store = PluginStore::new(plugins_dir.to_owned());
 
         // TODO: This is very similar to some logic in the badger module - look for consolidation opportunities.
         let manifests = store.catalogue_manifests()?;
@@ -60,9 +66,9 @@ impl PluginLookup {
         &self,
         plugins_dir: &Path,
     ) -> PluginLookupResult<PluginManifest> {
-        let url = plugins_repo_url()?;
+        let url = configured_plugins_repo_url(plugins_dir)?;
         tracing::info!("Pulling manifest for plugin {} from {url}", self.name);
-        fetch_plugins_repo(&url, plugins_dir, false)
+        fetch_plugins_repo(&url, plugins_dir, false, None)
             .await
             .map_err(|e| {
                 Error::ConnectionFailed(ConnectionFailedError::new(url.to_string(), e.to_string()))
@@ -116,6 +122,28 @@ pub fn plugins_repo_url() -> Result<Url, url::ParseError> {
     Url::parse(SPIN_PLUGINS_REPO)
 }
 
+/// The URL of the plugins repository to fetch from: the one saved with
+/// [`save_plugins_repo_url`], such as an internal mirror, or else the central
+/// repository.
+pub fn configured_plugins_repo_url(plugins_dir: &Path) -> anyhow::Result<Url> {
+    let path = PluginStore::new(plugins_dir).plugins_repo_url_file();
+    match std::fs::read_to_string(&path) {
+        Ok(text) => Url::parse(text.trim())
+            .with_context(|| format!("Invalid plugins repository URL in {}", path.display())),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(plugins_repo_url()?),
+        Err(e) => Err(e).with_context(|| format!("Failed to read {}", path.display())),
+    }
+}
+
+/// Saves `repo_url` as the plugins repository to fetch from, including when
+/// the local copy of it has to be cloned again.
+pub fn save_plugins_repo_url(plugins_dir: &Path, repo_url: &Url) -> anyhow::Result<()> {
+    let path = PluginStore::new(plugins_dir).plugins_repo_url_file();
+    std::fs::create_dir_all(plugins_dir)?;
+    std::fs::write(&path, format!("{repo_url}\n"))
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
 #[cfg(not(test))]
 fn accept_as_repo(git_root: &Path) -> bool {
     git_root.join(".git").exists()
@@ -126,13 +154,17 @@ fn accept_as_repo(git_root: &Path) -> bool {
     git_root.join(".git").exists() || git_root.join("_spin_test_dot_git").exists()
 }
 
+/// Clones the plugins repository from `repo_url` if there is no local copy
+/// yet. Otherwise, if `update` is set, pulls only the changes made since the
+/// copy was last updated. Git connects through `proxy` if one is given.
 pub async fn fetch_plugins_repo(
     repo_url: &Url,
     plugins_dir: &Path,
     update: bool,
+    proxy: Option<&Url>,
 ) -> anyhow::Result<()> {
     let git_root = plugin_manifests_repo_path(plugins_dir);
-    let git_source = GitSource::new(repo_url, None, &git_root);
+    let git_source = GitSource::new(repo_url, None, &git_root).with_proxy(proxy.cloned());
     if accept_as_repo(&git_root) {
         if update {
             git_source.pull().await?;
@@ -143,6 +175,44 @@ pub async fn fetch_plugins_repo(
     Ok(())
 }
 
+/// Replaces the local copy of the plugins repository with a clone of
+/// `repo_url`, such as an internal mirror. Later updates pull from the new
+/// clone, so the repository stays in use until replaced again.
+pub async fn replace_plugins_repo(
+    repo_url: &Url,
+    plugins_dir: &Path,
+    proxy: Option<&Url>,
+) -> anyhow::Result<()> {
+    let git_root = plugin_manifests_repo_path(plugins_dir);
+    // Clone alongside the current copy, so that it is kept if cloning fails.
+    let new_git_root = plugins_dir.join(format!("{PLUGINS_REPO_LOCAL_DIRECTORY}.new"));
+    tokio::fs::remove_dir_all(&new_git_root).await.ok();
+    GitSource::new(repo_url, None, &new_git_root)
+        .with_proxy(proxy.cloned())
+        .clone_repo()
+        .await?;
+    tokio::fs::remove_dir_all(&git_root).await.ok();
+    tokio::fs::rename(&new_git_root, &git_root).await?;
+    Ok(())
+}
+
+/// Discards the local copy of the plugins repository and clones it again in
+/// full, from wherever it was cloned from before, or else from
+/// `default_repo_url`.
+pub async fn refresh_plugins_repo(
+    default_repo_url: &Url,
+    plugins_dir: &Path,
+    proxy: Option<&Url>,
+) -> anyhow::Result<()> {
+    let git_root = plugin_manifests_repo_path(plugins_dir);
+    let repo_url = match accept_as_repo(&git_root) {
+        true => origin_url(&git_root).await.ok(),
+        false => None,
+    };
+    let repo_url = repo_url.as_ref().unwrap_or(default_repo_url);
+    replace_plugins_repo(repo_url, plugins_dir, proxy).await
+}
+
 fn plugin_manifests_repo_path(plugins_dir: &Path) -> PathBuf {
     plugins_dir.join(PLUGINS_REPO_LOCAL_DIRECTORY)
 }
@@ -180,6 +250,7 @@ fn null_version() -> semver::Version {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::testing;
 
     const TEST_NAME: &str = "some-spin-ver-some-not";
     const TESTS_STORE_DIR: &str = "tests";
@@ -242,6 +313,67 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn updates_pull_into_the_existing_clone() -> anyhow::Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let origin = temp_dir.path().join("origin");
+        let plugins_dir = temp_dir.path().join("plugins");
+        std::fs::create_dir(&origin)?;
+        std::fs::write(origin.join("first.json"), "{}")?;
+        testing::init_repo(&origin, "main");
+        let url = Url::from_directory_path(&origin).unwrap();
+
+        fetch_plugins_repo(&url, &plugins_dir, true, None).await?;
+        let git_root = plugin_manifests_repo_path(&plugins_dir);
+        std::fs::write(git_root.join("local.txt"), "kept")?;
+
+        std::fs::write(origin.join("second.json"), "{}")?;
+        testing::commit_all(&origin, "second");
+        fetch_plugins_repo(&url, &plugins_dir, true, None).await?;
+        assert!(git_root.join("second.json").exists());
+        assert!(git_root.join("local.txt").exists());
+
+        // A full refresh clones again from the same origin, whatever the default.
+        let default_url = Url::parse("https://example.com/not-a-repo")?;
+        refresh_plugins_repo(&default_url, &plugins_dir, None).await?;
+        assert!(git_root.join("second.json").exists());
+        assert!(!git_root.join("local.txt").exists());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn missing_clones_are_cloned_from_the_saved_repository() -> anyhow::Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let mirror = temp_dir.path().join("mirror");
+        let plugins_dir = temp_dir.path().join("plugins");
+        let manifest_dir = mirror
+            .join(PLUGINS_REPO_MANIFESTS_DIRECTORY)
+            .join("example");
+        std::fs::create_dir_all(&manifest_dir)?;
+        let manifest = testing::manifest_json("1.0.0", serde_json::json!([]));
+        std::fs::write(manifest_dir.join("example.json"), manifest.to_string())?;
+        testing::init_repo(&mirror, "main");
+        let url = Url::from_directory_path(&mirror).unwrap();
+        save_plugins_repo_url(&plugins_dir, &url)?;
+        assert_eq!(url, configured_plugins_repo_url(&plugins_dir)?);
+
+        let lookup = PluginLookup::new("example", None);
+        let resolved = lookup.resolve_manifest_exact(&plugins_dir).await?;
+        assert_eq!("1.0.0", resolved.version);
+
+        // Failures name the saved repository, not the central one.
+        std::fs::remove_dir_all(plugin_manifests_repo_path(&plugins_dir))?;
+        let missing = Url::from_directory_path(temp_dir.path().join("missing")).unwrap();
+        save_plugins_repo_url(&plugins_dir, &missing)?;
+        let err = lookup
+            .resolve_manifest_exact(&plugins_dir)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::ConnectionFailed(_)));
+        assert!(err.to_string().contains(missing.as_str()), "{err}");
+        Ok(())
+    }
+
     #[tokio::test]
     async fn if_non_existent_version_given_then_error() -> PluginLookupResult<()> {
         let lookup = PluginLookup::new(TEST_NAME, Some(semver::Version::parse("177.7.7").unwrap()));
@@ -1,19 +1,23 @@
 use crate::{
     error::*,
+    git::GitSource,
     lookup::PluginLookup,
     manifest::{warn_unsupported_version, PluginManifest, PluginPackage},
+    progress::DownloadProgress,
+    signature,
     store::PluginStore,
     SPIN_INTERNAL_COMMANDS,
 };
 
 use anyhow::{anyhow, bail, Context, Result};
 use path_absolutize::Absolutize;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use spin_common::sha256;
 use std::{
     fs::{self, File},
-    io::{copy, Cursor},
+    io::Write,
     path::{Path, PathBuf},
+    time::Duration,
 };
 use tempfile::{tempdir, TempDir};
 use url::Url;
@@ -21,6 +25,14 @@ use url::Url;
 // Url scheme prefix of a plugin that is installed from a local source
 const URL_FILE_SCHEME: &str = "file";
 
+/// How many times a plugin download that fails on a network error is retried
+/// by default.
+pub const DEFAULT_DOWNLOAD_RETRIES: u32 = 3;
+
+/// How long to wait before retrying a failed download. Each further retry
+/// waits twice as long as the last.
+const DOWNLOAD_RETRY_DELAY: Duration = Duration::from_secs(1);
+
 /// Location of manifest of the plugin to be installed.
 pub enum ManifestLocation {
     /// Plugin manifest can be copied from a local path.
@@ -29,6 +41,15 @@ pub enum ManifestLocation {
     Remote(Url),
     /// Plugin manifest lives in the centralized plugins repository
     PluginsRepository(PluginLookup),
+    /// Plugin manifest should be cloned from a Git repository.
+    Git {
+        url: Url,
+        /// Branch or tag to clone, rather than the default branch.
+        rev: Option<String>,
+        /// Path of the manifest within the repository. If not given, the
+        /// repository root must contain exactly one plugin manifest.
+        manifest_path: Option<PathBuf>,
+    },
 }
 
 impl ManifestLocation {
@@ -47,28 +68,109 @@ impl ManifestLocation {
                 url: url.to_owned(),
             },
             Self::PluginsRepository(_) => RawInstallRecord::PluginsRepository,
+            Self::Git {
+                url,
+                rev,
+                manifest_path,
+            } => RawInstallRecord::Git {
+                url: url.to_owned(),
+                rev: rev.to_owned(),
+                manifest_path: manifest_path.to_owned(),
+            },
         }
     }
 }
 
-#[derive(Serialize)]
+/// What is recorded about the installation of a plugin.
+#[derive(Serialize, Deserialize)]
+struct InstallRecord {
+    #[serde(flatten)]
+    source: RawInstallRecord,
+    /// Missing from records written before acceptances were recorded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    license_acceptance: Option<LicenseAcceptance>,
+}
+
+/// The acceptance of a plugin's license. Installing a plugin accepts it, either
+/// in answer to the install prompt or with the flags that skip the prompt.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LicenseAcceptance {
+    /// The license as named in the plugin's manifest.
+    pub license: String,
+    pub accepted_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Serialize, Deserialize)]
 #[serde(rename = "snake_case", tag = "source")]
 pub(crate) enum RawInstallRecord {
     PluginsRepository,
-    Remote { url: Url },
-    Local { file: PathBuf },
+    Remote {
+        url: Url,
+    },
+    Local {
+        file: PathBuf,
+    },
+    Git {
+        url: Url,
+        rev: Option<String>,
+        manifest_path: Option<PathBuf>,
+    },
 }
 
 /// Provides accesses to functionality to inspect and manage the installation of plugins.
 pub struct PluginManager {
     store: PluginStore,
+    download_retries: u32,
+    require_signed: bool,
+    /// Client for downloading manifests and packages. Unless a proxy is set
+    /// with `with_proxy`, it honours the HTTP(S)_PROXY and NO_PROXY
+    /// environment variables.
+    http_client: reqwest::Client,
+    proxy: Option<Url>,
 }
 
 impl PluginManager {
     /// Creates a `PluginManager` with the default install location.
     pub fn try_default() -> anyhow::Result<Self> {
         let store = PluginStore::try_default()?;
-        Ok(Self { store })
+        Ok(Self {
+            store,
+            download_retries: DEFAULT_DOWNLOAD_RETRIES,
+            require_signed: false,
+            http_client: reqwest::Client::new(),
+            proxy: None,
+        })
+    }
+
+    /// Downloads plugins and manifests through `proxy`, whatever the
+    /// environment says.
+    pub fn with_proxy(self, proxy: Url) -> Result<Self> {
+        let http_client = reqwest::Client::builder()
+            .proxy(reqwest::Proxy::all(proxy.clone())?)
+            .build()?;
+        Ok(Self {
+            http_client,
+            proxy: Some(proxy),
+            ..self
+        })
+    }
+
+    /// Sets how many times a plugin download that fails on a network error
+    /// is retried before giving up.
+    pub fn with_download_retries(self, download_retries: u32) -> Self {
+        Self {
+            download_retries,
+            ..self
+        }
+    }
+
+    /// Sets whether plugins must be signed with a trusted key to be installed.
+    /// Signatures made with trusted keys are checked either way.
+    pub fn with_required_signatures(self, require_signed: bool) -> Self {
+        Self {
+            require_signed,
+            ..self
+        }
     }
 
     /// Returns the underlying store object
@@ -81,6 +183,7 @@ impl PluginManager {
     /// the appropriately named and versioned plugin manifest. Parses the plugin manifest to get the
     /// appropriate source for the machine OS and architecture. Verifies the checksum of the source,
     /// unpacks and installs it into the plugins directory.
+    /// The caller must have had the plugin's license accepted, which is recorded with the plugin.
     /// Returns name of plugin that was successfully installed.
     pub async fn install(
         &self,
@@ -105,19 +208,68 @@ impl PluginManager {
                     );
                 }
             }
-            _ => download_plugin(&plugin_manifest.name(), &temp_dir, &target).await?,
+            _ => {
+                download_plugin(
+                    &self.http_client,
+                    &plugin_manifest.name(),
+                    &temp_dir,
+                    &target,
+                    self.download_retries,
+                )
+                .await?
+            }
         };
-        verify_checksum(&plugin_tarball_path, &plugin_package.sha256)?;
+        verify_checksum(&plugin_tarball_path, &plugin_package.sha256)
+            .with_context(|| format!("Failed to verify package {target}"))?;
+        self.verify_signature(plugin_manifest, plugin_package, &plugin_tarball_path)
+            .with_context(|| format!("Failed to verify package {target}"))?;
+
+        // Keep any installed version until the new one is in place, so that a
+        // failed upgrade doesn't leave the plugin broken.
+        let name = plugin_manifest.name();
+        let backup = self.store.back_up_plugin(&name)?;
+        if let Err(e) = self.unpack(plugin_manifest, &plugin_tarball_path) {
+            let Some(backup) = backup else {
+                return Err(e);
+            };
+            backup
+                .restore(&self.store)
+                .with_context(|| format!("Failed to restore the previous version of {name}"))?;
+            terminal::warn!(
+                "Installing plugin '{name}' failed, so the previous version was restored."
+            );
+            return Err(e);
+        }
+        if let Some(backup) = backup {
+            backup.discard();
+        }
+        self.write_install_record(&name, source, plugin_manifest.license());
+
+        Ok(name)
+    }
 
+    /// Unpacks the plugin package at `plugin_tarball_path` into the plugins
+    /// directory and saves its manifest there.
+    fn unpack(
+        &self,
+        plugin_manifest: &PluginManifest,
+        plugin_tarball_path: &PathBuf,
+    ) -> Result<()> {
+        let name = plugin_manifest.name();
         self.store
-            .untar_plugin(&plugin_tarball_path, &plugin_manifest.name())
+            .untar_plugin(plugin_tarball_path, &name)
             .with_context(|| format!("Failed to untar {}", plugin_tarball_path.display()))?;
+        let binary = self.store.installed_binary_path(&name);
+        if !binary.is_file() {
+            bail!(
+                "Package for plugin '{name}' does not contain its executable {}",
+                binary.display()
+            );
+        }
 
         // Save manifest to installed plugins directory
         self.store.add_manifest(plugin_manifest)?;
-        self.write_install_record(&plugin_manifest.name(), source);
-
-        Ok(plugin_manifest.name())
+        Ok(())
     }
 
     /// Uninstalls a plugin with a given name, removing it and it's manifest from the local plugins
@@ -175,9 +327,42 @@ impl PluginManager {
 
         warn_unsupported_version(plugin_manifest, spin_version, override_compatibility_check)?;
 
+        // Refuse unsigned plugins before downloading them, when signatures are
+        // required. The signature itself is checked after the download.
+        if self.require_signed {
+            signature::signed_by_trusted_key(
+                plugin_manifest,
+                get_package(plugin_manifest)?,
+                &self.trusted_keys()?,
+            )?;
+        }
+
         Ok(InstallAction::Continue)
     }
 
+    fn trusted_keys(&self) -> Result<Vec<Vec<u8>>> {
+        signature::trusted_keys(&self.store.trusted_keys_file())
+    }
+
+    /// Checks the signature of a downloaded package, if it is signed with a
+    /// trusted key. Packages without one are refused only if signatures are
+    /// required.
+    fn verify_signature(
+        &self,
+        manifest: &PluginManifest,
+        package: &PluginPackage,
+        package_file: &Path,
+    ) -> Result<()> {
+        match signature::signed_by_trusted_key(manifest, package, &self.trusted_keys()?) {
+            Ok(signed) => signed.verify(package_file),
+            Err(e) if self.require_signed => Err(e),
+            Err(e) => {
+                tracing::info!("Not checking signature: {e:#}");
+                Ok(())
+            }
+        }
+    }
+
     /// Fetches a manifest from a local, remote, or repository location and returned the parsed
     /// PluginManifest object.
     pub async fn get_manifest(
@@ -189,7 +374,9 @@ impl PluginManager {
         let plugin_manifest = match manifest_location {
             ManifestLocation::Remote(url) => {
                 tracing::info!("Pulling manifest for plugin from {url}");
-                reqwest::get(url.as_ref())
+                self.http_client
+                    .get(url.as_ref())
+                    .send()
                     .await
                     .map_err(|e| {
                         Error::ConnectionFailed(ConnectionFailedError::new(
@@ -216,20 +403,21 @@ impl PluginManager {
             }
             ManifestLocation::Local(path) => {
                 tracing::info!("Pulling manifest for plugin from {}", path.display());
-                let file = File::open(path).map_err(|e| {
-                    Error::NotFound(NotFoundError::new(
-                        None,
-                        path.display().to_string(),
-                        e.to_string(),
-                    ))
-                })?;
-                serde_json::from_reader(file).map_err(|e| {
-                    Error::InvalidManifest(InvalidManifestError::new(
-                        None,
-                        path.display().to_string(),
-                        e.to_string(),
-                    ))
-                })?
+                read_manifest(path)?
+            }
+            ManifestLocation::Git {
+                url,
+                rev,
+                manifest_path,
+            } => {
+                tracing::info!("Pulling manifest for plugin from Git repository {url}");
+                clone_manifest(
+                    url,
+                    rev.as_deref(),
+                    manifest_path.as_deref(),
+                    self.proxy.as_ref(),
+                )
+                .await?
             }
             ManifestLocation::PluginsRepository(lookup) => {
                 lookup
@@ -257,11 +445,38 @@ impl PluginManager {
         Ok(locker)
     }
 
-    fn write_install_record(&self, plugin_name: &str, source: &ManifestLocation) {
+    /// The URL that an installed plugin's manifest was downloaded from, if it
+    /// wasn't installed from the plugins repository or a local file.
+    pub fn installed_from_url(&self, plugin_name: &str) -> Option<Url> {
+        match self.read_install_record(plugin_name)?.source {
+            RawInstallRecord::Remote { url } => Some(url),
+            _ => None,
+        }
+    }
+
+    /// The license accepted when an installed plugin was installed, and when
+    /// that was, if it was recorded.
+    pub fn license_acceptance(&self, plugin_name: &str) -> Option<LicenseAcceptance> {
+        self.read_install_record(plugin_name)?.license_acceptance
+    }
+
+    fn read_install_record(&self, plugin_name: &str) -> Option<InstallRecord> {
+        let install_record_path = self.store.installation_record_file(plugin_name);
+        let record_text = std::fs::read_to_string(install_record_path).ok()?;
+        serde_json::from_str(&record_text).ok()
+    }
+
+    fn write_install_record(&self, plugin_name: &str, source: &ManifestLocation, license: &str) {
         let install_record_path = self.store.installation_record_file(plugin_name);
 
         // A failure here shouldn't fail the install
-        let install_record = source.to_install_record();
+        let install_record = InstallRecord {
+            source: source.to_install_record(),
+            license_acceptance: Some(LicenseAcceptance {
+                license: license.to_owned(),
+                accepted_at: chrono::Utc::now(),
+            }),
+        };
         if let Ok(record_text) = serde_json::to_string_pretty(&install_record) {
             _ = std::fs::write(install_record_path, record_text);
         }
@@ -322,57 +537,228 @@ pub enum InstallAction {
     NoAction { name: String, version: String },
 }
 
+fn read_manifest(path: &Path) -> PluginLookupResult<PluginManifest> {
+    let file = File::open(path).map_err(|e| {
+        Error::NotFound(NotFoundError::new(
+            None,
+            path.display().to_string(),
+            e.to_string(),
+        ))
+    })?;
+    serde_json::from_reader(file).map_err(|e| {
+        Error::InvalidManifest(InvalidManifestError::new(
+            None,
+            path.display().to_string(),
+            e.to_string(),
+        ))
+    })
+}
+
+/// Clones the Git repository at `url` and reads the plugin manifest from it.
+async fn clone_manifest(
+    url: &Url,
+    rev: Option<&str>,
+    manifest_path: Option<&Path>,
+    proxy: Option<&Url>,
+) -> PluginLookupResult<PluginManifest> {
+    let temp_dir = tempdir().map_err(anyhow::Error::from)?;
+    let repo_dir = temp_dir.path().join("repo");
+    GitSource::new(url, rev.map(str::to_owned), &repo_dir)
+        .with_proxy(proxy.cloned())
+        .clone_repo()
+        .await
+        .map_err(|e| {
+            Error::ConnectionFailed(ConnectionFailedError::new(url.to_string(), e.to_string()))
+        })?;
+
+    if let Some(manifest_path) = manifest_path {
+        return read_manifest(&repo_dir.join(manifest_path));
+    }
+
+    // Without a path, look for the one JSON file at the root that is a plugin
+    // manifest, skipping the likes of package.json.
+    let mut manifests = vec![];
+    for entry in fs::read_dir(&repo_dir).map_err(anyhow::Error::from)? {
+        let path = entry.map_err(anyhow::Error::from)?.path();
+        if path.extension().is_some_and(|ext| ext == "json") {
+            if let Ok(manifest) = read_manifest(&path) {
+                manifests.push((path, manifest));
+            }
+        }
+    }
+    match manifests.len() {
+        0 => Err(Error::NotFound(NotFoundError::new(
+            None,
+            url.to_string(),
+            "there is no plugin manifest at the root of the repository".to_owned(),
+        ))),
+        1 => Ok(manifests.remove(0).1),
+        _ => {
+            let names = manifests
+                .iter()
+                .filter_map(|(path, _)| path.file_name())
+                .map(|name| name.to_string_lossy())
+                .collect::<Vec<_>>()
+                .join(", ");
+            Err(Error::Other(anyhow!(
+                "Git repository {url} has several plugin manifests ({names}), so the manifest path must be given"
+            )))
+        }
+    }
+}
+
 /// Gets the appropriate package for the running OS and Arch if exists
 pub fn get_package(plugin_manifest: &PluginManifest) -> Result<&PluginPackage> {
     use std::env::consts::{ARCH, OS};
-    plugin_manifest
-        .packages
+    find_package(plugin_manifest, OS, ARCH)
+}
+
+/// Gets the package for `os` and `arch`, or explains which platforms the
+/// plugin does support.
+fn find_package<'a>(
+    plugin_manifest: &'a PluginManifest,
+    os: &str,
+    arch: &str,
+) -> Result<&'a PluginPackage> {
+    let packages = plugin_manifest.packages();
+    if let Some(package) = packages.iter().find(|p| p.os() == os && p.arch() == arch) {
+        return Ok(package);
+    }
+    let name = plugin_manifest.name();
+    if packages.is_empty() {
+        return Err(IncompatibleError::new(format!(
+            "Plugin '{name}' does not provide packages for any platform."
+        ))
+        .into());
+    }
+    let supported = packages
         .iter()
-        .find(|p| p.os.rust_name() == OS && p.arch.rust_name() == ARCH)
-        .ok_or_else(|| {
-            anyhow!("This plugin does not support this OS ({OS}) or architecture ({ARCH}).")
-        })
+        .map(|p| format!("{}/{}", p.os(), p.arch()))
+        .collect::<Vec<_>>()
+        .join(", ");
+    Err(IncompatibleError::new(format!(
+        "Plugin '{name}' supports {supported}, but you are on {os}/{arch}."
+    ))
+    .into())
+}
+
+/// Downloads the package at `target_url`, retrying up to `retries` times if
+/// the download fails in a way that might not happen again.
+async fn download_plugin(
+    client: &reqwest::Client,
+    name: &str,
+    temp_dir: &TempDir,
+    target_url: &str,
+    retries: u32,
+) -> Result<PathBuf> {
+    let mut attempt = 0;
+    let mut delay = DOWNLOAD_RETRY_DELAY;
+    loop {
+        let mut e = match try_download_plugin(client, name, temp_dir, target_url).await {
+            Err(e) if attempt < retries && is_transient(&e) => {
+                attempt += 1;
+                terminal::warn!(
+                    "Downloading plugin '{name}' failed, retrying in {}s: {e:#}",
+                    delay.as_secs()
+                );
+                tokio::time::sleep(delay).await;
+                delay *= 2;
+                continue;
+            }
+            Err(e) => e,
+            result => return result,
+        };
+        if attempt > 0 {
+            let attempts = attempt + 1;
+            e = e.context(format!(
+                "Failed to download plugin '{name}' after {attempts} attempts"
+            ));
+        }
+        if is_connect_error(&e) {
+            e = e.context(
+                "Could not connect to download the plugin. If you are behind a proxy, set the HTTPS_PROXY environment variable or pass --proxy",
+            );
+        }
+        return Err(e);
+    }
+}
+
+fn is_connect_error(e: &anyhow::Error) -> bool {
+    e.downcast_ref::<reqwest::Error>()
+        .is_some_and(|e| e.is_connect())
+}
+
+/// Whether a failed download might succeed if tried again: that is, if the
+/// connection failed or the server had a problem.
+fn is_transient(e: &anyhow::Error) -> bool {
+    let Some(e) = e.downcast_ref::<reqwest::Error>() else {
+        return false;
+    };
+    match e.status() {
+        Some(status) => {
+            status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS
+        }
+        None => e.is_connect() || e.is_timeout() || e.is_request() || e.is_body(),
+    }
 }
 
-async fn download_plugin(name: &str, temp_dir: &TempDir, target_url: &str) -> Result<PathBuf> {
+async fn try_download_plugin(
+    client: &reqwest::Client,
+    name: &str,
+    temp_dir: &TempDir,
+    target_url: &str,
+) -> Result<PathBuf> {
     tracing::trace!("Trying to get tar file for plugin '{name}' from {target_url}");
-    let plugin_bin = reqwest::get(target_url).await?;
+    let mut plugin_bin = client.get(target_url).send().await?;
     if !plugin_bin.status().is_success() {
         match plugin_bin.status() {
             reqwest::StatusCode::NOT_FOUND => bail!("The download URL specified in the plugin manifest was not found ({target_url} returned HTTP error 404). Please contact the plugin author."),
-            _ => bail!("HTTP error {} when downloading plugin from {target_url}", plugin_bin.status()),
+            status => return Err(plugin_bin.error_for_status().unwrap_err()).with_context(|| format!("HTTP error {status} when downloading plugin from {target_url}")),
         }
     }
 
-    let mut content = Cursor::new(plugin_bin.bytes().await?);
     let dir = temp_dir.path();
     let mut plugin_file = dir.join(name);
     plugin_file.set_extension("tar.gz");
     let mut temp_file = File::create(&plugin_file)?;
-    copy(&mut content, &mut temp_file)?;
+    let mut progress = DownloadProgress::start(name, plugin_bin.content_length());
+    while let Some(chunk) = plugin_bin.chunk().await? {
+        temp_file.write_all(&chunk)?;
+        progress.advance(chunk.len() as u64);
+    }
     Ok(plugin_file)
 }
 
 fn verify_checksum(plugin_file: &Path, expected_sha256: &str) -> Result<()> {
     let actual_sha256 = sha256::hex_digest_from_file(plugin_file)
         .with_context(|| format!("Cannot get digest for {}", plugin_file.display()))?;
-    if actual_sha256 == expected_sha256 {
+    // Digests are published in either case.
+    if actual_sha256.eq_ignore_ascii_case(expected_sha256) {
         tracing::info!("Package checksum verified successfully");
         Ok(())
     } else {
-        Err(anyhow!("Checksum did not match, aborting installation."))
+        Err(anyhow!(
+            "Checksum did not match (expected {expected_sha256}, got {actual_sha256}), aborting installation."
+        ))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::testing;
 
     #[tokio::test]
     async fn good_error_when_tarball_404s() -> anyhow::Result<()> {
         let temp_dir = tempdir()?;
         let store = PluginStore::new(temp_dir.path());
-        let manager = PluginManager { store };
+        let manager = PluginManager {
+            store,
+            download_retries: 0,
+            require_signed: false,
+            http_client: reqwest::Client::new(),
+            proxy: None,
+        };
 
         let bad_manifest: PluginManifest = serde_json::from_str(include_str!(
             "../tests/nonexistent-url/nonexistent-url.json"
@@ -396,4 +782,242 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn failed_upgrade_restores_previous_version() -> anyhow::Result<()> {
+        let temp_dir = tempdir()?;
+        let store = PluginStore::new(temp_dir.path().join("plugins"));
+        let binary = store.installed_binary_path("example");
+        fs::create_dir_all(binary.parent().unwrap())?;
+        fs::write(&binary, "old")?;
+        let manifest = |version: &str, package: &Path| {
+            let url = Url::from_file_path(package).unwrap();
+            let sha256 = sha256::hex_digest_from_file(package).unwrap_or_default();
+            testing::manifest(
+                version,
+                serde_json::json!([testing::package_json(
+                    "linux",
+                    "amd64",
+                    url.as_str(),
+                    &sha256
+                )]),
+            )
+        };
+        let old_manifest = manifest("1.0.0", &binary);
+        store.add_manifest(&old_manifest)?;
+        let manager = PluginManager {
+            store,
+            download_retries: 0,
+            require_signed: false,
+            http_client: reqwest::Client::new(),
+            proxy: None,
+        };
+
+        let package = temp_dir.path().join("example.tar.gz");
+        fs::write(&package, "not a tarball")?;
+        let new_manifest = manifest("2.0.0", &package);
+        let result = manager
+            .install(
+                &new_manifest,
+                &new_manifest.packages[0],
+                &ManifestLocation::Local(package.clone()),
+            )
+            .await;
+
+        assert!(result.is_err());
+        assert_eq!("old", fs::read_to_string(&binary)?);
+        assert_eq!(old_manifest, manager.store.read_plugin_manifest("example")?);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn manifests_are_found_in_git_repositories() -> anyhow::Result<()> {
+        let temp_dir = tempdir()?;
+        let repo = temp_dir.path();
+        let manifest = testing::manifest_json("1.0.0", serde_json::json!([]));
+        std::fs::write(repo.join("example.json"), manifest.to_string())?;
+        std::fs::write(repo.join("package.json"), "{}")?;
+        std::fs::create_dir(repo.join("nested"))?;
+        std::fs::write(repo.join("nested/other.json"), manifest.to_string())?;
+        testing::init_repo(repo, "trunk");
+
+        let url = Url::from_directory_path(repo).unwrap();
+        let found = clone_manifest(&url, None, None, None).await?;
+        assert_eq!("example", found.name());
+        let found = clone_manifest(
+            &url,
+            Some("trunk"),
+            Some(Path::new("nested/other.json")),
+            None,
+        )
+        .await?;
+        assert_eq!("example", found.name());
+        assert!(clone_manifest(&url, Some("nonexistent"), None, None)
+            .await
+            .is_err());
+        Ok(())
+    }
+
+    const PLUGIN_RESPONSE: &str =
+        "HTTP/1.1 200 OK\r\nContent-Length: 6\r\nConnection: close\r\n\r\nplugin";
+
+    /// Serves `responses` to successive requests, returning the server's
+    /// address and the request lines it received.
+    fn serve(
+        responses: Vec<&'static str>,
+    ) -> anyhow::Result<(std::net::SocketAddr, std::thread::JoinHandle<Vec<String>>)> {
+        use std::io::{BufRead, BufReader};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0")?;
+        let addr = listener.local_addr()?;
+        let server = std::thread::spawn(move || {
+            let mut requests = vec![];
+            for response in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut request = String::new();
+                reader.read_line(&mut request).unwrap();
+                requests.push(request.trim_end().to_owned());
+                let mut line = String::new();
+                while reader.read_line(&mut line).unwrap() > 2 {
+                    line.clear();
+                }
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+            requests
+        });
+        Ok((addr, server))
+    }
+
+    #[tokio::test]
+    async fn downloads_are_retried_after_server_errors() -> anyhow::Result<()> {
+        let (addr, server) = serve(vec![
+            "HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+            PLUGIN_RESPONSE,
+        ])?;
+        let url = format!("http://{addr}/plugin.tar.gz");
+
+        let temp_dir = tempdir()?;
+        let path = download_plugin(&reqwest::Client::new(), "example", &temp_dir, &url, 1).await;
+        server.join().unwrap();
+        assert_eq!("plugin", std::fs::read_to_string(path?)?);
+        Ok(())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn failed_connections_suggest_a_proxy_after_retrying() -> anyhow::Result<()> {
+        // Nothing listens on the address once the listener is dropped.
+        let addr = std::net::TcpListener::bind("127.0.0.1:0")?.local_addr()?;
+        let url = format!("http://{addr}/plugin.tar.gz");
+
+        let temp_dir = tempdir()?;
+        let err = download_plugin(
+            &reqwest::Client::new(),
+            "example",
+            &temp_dir,
+            &url,
+            DEFAULT_DOWNLOAD_RETRIES,
+        )
+        .await
+        .unwrap_err();
+        let message = format!("{err:#}");
+        assert!(message.contains("HTTPS_PROXY"), "{message}");
+        assert!(
+            message.contains(&format!("after {} attempts", DEFAULT_DOWNLOAD_RETRIES + 1)),
+            "{message}"
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn downloads_go_through_the_proxy() -> anyhow::Result<()> {
+        let (addr, server) = serve(vec![PLUGIN_RESPONSE])?;
+        let temp_dir = tempdir()?;
+        let manager = PluginManager {
+            store: PluginStore::new(temp_dir.path()),
+            download_retries: 0,
+            require_signed: false,
+            http_client: reqwest::Client::new(),
+            proxy: None,
+        }
+        .with_proxy(Url::parse(&format!("http://{addr}"))?)?;
+
+        let url = "http://plugins.invalid/plugin.tar.gz";
+        let path = download_plugin(&manager.http_client, "example", &temp_dir, url, 0).await;
+        let requests = server.join().unwrap();
+        assert_eq!("plugin", std::fs::read_to_string(path?)?);
+        assert_eq!(vec![format!("GET {url} HTTP/1.1")], requests);
+        Ok(())
+    }
+
+    #[test]
+    fn license_acceptance_is_recorded_with_the_install() -> anyhow::Result<()> {
+        let temp_dir = tempdir()?;
+        let store = PluginStore::new(temp_dir.path());
+        fs::create_dir_all(store.plugin_subdirectory_path("example"))?;
+        let manager = PluginManager {
+            store,
+            download_retries: 0,
+            require_signed: false,
+            http_client: reqwest::Client::new(),
+            proxy: None,
+        };
+        let url = Url::parse("https://example.com/example.json")?;
+        let before = chrono::Utc::now();
+        manager.write_install_record("example", &ManifestLocation::Remote(url.clone()), "MIT");
+
+        let acceptance = manager.license_acceptance("example").unwrap();
+        assert_eq!("MIT", acceptance.license);
+        assert!(acceptance.accepted_at >= before);
+        assert_eq!(Some(url), manager.installed_from_url("example"));
+
+        // Records written before acceptances were recorded still say where the
+        // plugin came from.
+        fs::write(
+            manager.store.installation_record_file("example"),
+            r#"{ "source": "Remote", "url": "https://example.com/old.json" }"#,
+        )?;
+        assert_eq!(None, manager.license_acceptance("example"));
+        assert_eq!(
+            "https://example.com/old.json",
+            manager.installed_from_url("example").unwrap().as_str()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn missing_platforms_are_explained() -> anyhow::Result<()> {
+        let manifest = testing::manifest(
+            "1.0.0",
+            serde_json::json!([
+                testing::package_json("linux", "amd64", "https://example.com/a", "a"),
+                testing::package_json("macos", "aarch64", "https://example.com/b", "b"),
+            ]),
+        );
+
+        let package = find_package(&manifest, "macos", "aarch64")?;
+        assert_eq!("https://example.com/b", package.url());
+
+        let err = find_package(&manifest, "linux", "aarch64").unwrap_err();
+        assert_eq!(
+            "Plugin 'example' supports linux/x86_64, macos/aarch64, but you are on linux/aarch64.",
+            err.to_string()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn checksums_are_compared_ignoring_case() -> anyhow::Result<()> {
+        let temp_dir = tempdir()?;
+        let path = temp_dir.path().join("plugin.tar.gz");
+        std::fs::write(&path, "plugin")?;
+        let digest = sha256::hex_digest_from_file(&path)?;
+
+        verify_checksum(&path, &digest.to_uppercase())?;
+        let err = verify_checksum(&path, &"0".repeat(64)).unwrap_err();
+        assert!(err.to_string().contains(&digest), "{err}");
+
+        Ok(())
+    }
 }
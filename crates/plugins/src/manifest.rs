@@ -1,11 +1,11 @@
 use std::io::IsTerminal;
 
-use anyhow::{anyhow, Context, Result};
+use anyhow::{Context, Result};
 use semver::{Version, VersionReq};
 use serde::{Deserialize, Serialize};
 use url::Url;
 
-use crate::PluginStore;
+use crate::{error::IncompatibleError, PluginStore};
 
 /// Expected schema of a plugin manifest. Should match the latest Spin plugin
 /// manifest JSON schema:
@@ -29,6 +29,9 @@ pub struct PluginManifest {
     license: String,
     /// Points to source package[s] of the plugin..
     pub(crate) packages: Vec<PluginPackage>,
+    /// Base64-encoded Ed25519 public key that the packages are signed with.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    public_key: Option<String>,
 }
 
 impl PluginManifest {
@@ -54,6 +57,14 @@ impl PluginManifest {
         Url::parse(self.homepage.as_deref()?).ok()
     }
 
+    pub fn packages(&self) -> &[PluginPackage] {
+        &self.packages
+    }
+
+    pub fn public_key(&self) -> Option<&str> {
+        self.public_key.as_deref()
+    }
+
     pub fn has_compatible_package(&self) -> bool {
         self.packages.iter().any(|p| p.matches_current_os_arch())
     }
@@ -83,12 +94,23 @@ pub struct PluginPackage {
     pub(crate) url: String,
     /// Checksum to verify the plugin before installation.
     pub(crate) sha256: String,
+    /// Base64-encoded Ed25519 signature of the plugin source tar file.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) signature: Option<String>,
 }
 
 impl PluginPackage {
     pub fn url(&self) -> String {
         self.url.clone()
     }
+    /// The OS the package runs on, named as in `std::env::consts::OS`.
+    pub fn os(&self) -> &'static str {
+        self.os.rust_name()
+    }
+    /// The architecture the package runs on, named as in `std::env::consts::ARCH`.
+    pub fn arch(&self) -> &'static str {
+        self.arch.rust_name()
+    }
     pub fn matches_current_os_arch(&self) -> bool {
         self.os.rust_name() == std::env::consts::OS
             && self.arch.rust_name() == std::env::consts::ARCH
@@ -192,9 +214,9 @@ fn inner_warn_unsupported_version(
                 terminal::warn!("Plugin is not compatible with this version of Spin (supported: {supported_on}, actual: {spin_version}). Check overridden ... continuing to install or execute plugin.");
             }
         } else {
-            return Err(anyhow!(
+            return Err(IncompatibleError::new(format!(
             "Plugin is not compatible with this version of Spin (supported: {supported_on}, actual: {spin_version}). Try running `spin plugins update && spin plugins upgrade --all` to install latest or override with `--override-compatibility-check`."
-        ));
+        )).into());
         }
     }
     Ok(())
@@ -0,0 +1,76 @@
+//! Pins hold installed plugins at the versions users depend on, so that
+//! upgrading every plugin leaves them alone.
+
+use std::collections::BTreeMap;
+
+use anyhow::{Context, Result};
+use semver::Version;
+use serde::{Deserialize, Serialize};
+
+use crate::PluginStore;
+
+/// The pinned version of each pinned plugin, by name.
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+#[serde(transparent)]
+pub struct PluginPins {
+    pins: BTreeMap<String, String>,
+}
+
+impl PluginPins {
+    /// Reads the pins recorded in `store`, if there are any.
+    pub fn load(store: &PluginStore) -> Result<Self> {
+        let path = store.pins_file();
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let text = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        serde_json::from_str(&text).with_context(|| format!("Invalid pins file {}", path.display()))
+    }
+
+    pub fn save(&self, store: &PluginStore) -> Result<()> {
+        let path = store.pins_file();
+        std::fs::create_dir_all(store.get_plugins_directory())?;
+        let text = serde_json::to_string_pretty(self)?;
+        std::fs::write(&path, text).with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// Pins the plugin named `name` at `version`, replacing any earlier pin.
+    pub fn pin(&mut self, name: &str, version: &Version) {
+        self.pins.insert(name.to_lowercase(), version.to_string());
+    }
+
+    /// Removes the pin on the plugin named `name`, returning whether there was
+    /// one.
+    pub fn unpin(&mut self, name: &str) -> bool {
+        self.pins.remove(&name.to_lowercase()).is_some()
+    }
+
+    pub fn pinned_version(&self, name: &str) -> Option<&str> {
+        self.pins.get(&name.to_lowercase()).map(String::as_str)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pins_round_trip() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let store = PluginStore::new(temp_dir.path().join("plugins"));
+        let mut pins = PluginPins::load(&store)?;
+        assert_eq!(None, pins.pinned_version("example"));
+
+        pins.pin("Example", &Version::new(1, 2, 3));
+        pins.pin("other", &Version::new(0, 1, 0));
+        assert!(pins.unpin("other"));
+        assert!(!pins.unpin("other"));
+        pins.save(&store)?;
+
+        let pins = PluginPins::load(&store)?;
+        assert_eq!(Some("1.2.3"), pins.pinned_version("example"));
+        assert_eq!(None, pins.pinned_version("other"));
+        Ok(())
+    }
+}
@@ -0,0 +1,91 @@
+//! Reports the progress of plugin downloads.
+
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+use indicatif::{HumanBytes, MultiProgress, ProgressBar, ProgressStyle};
+use is_terminal::IsTerminal;
+
+/// How often progress is logged when it can't be drawn as a bar.
+const LOG_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Shows how much of a plugin has been downloaded: as a bar on a terminal, or
+/// otherwise as an occasional log line.
+pub(crate) enum DownloadProgress {
+    Bar(ProgressBar),
+    Log {
+        name: String,
+        total: Option<u64>,
+        downloaded: u64,
+        last_logged: Instant,
+    },
+}
+
+impl DownloadProgress {
+    /// Starts reporting the download of plugin `name`, whose size is `total`
+    /// bytes if known.
+    pub fn start(name: &str, total: Option<u64>) -> Self {
+        if !std::io::stderr().is_terminal() {
+            return Self::Log {
+                name: name.to_owned(),
+                total,
+                downloaded: 0,
+                last_logged: Instant::now(),
+            };
+        }
+        let bar = match total {
+            Some(total) => ProgressBar::new(total).with_style(
+                ProgressStyle::with_template(
+                    "{msg} [{bar:30}] {bytes}/{total_bytes} ({bytes_per_sec})",
+                )
+                .unwrap()
+                .progress_chars("=> "),
+            ),
+            None => ProgressBar::new_spinner()
+                .with_style(ProgressStyle::with_template("{msg} {spinner} {bytes}").unwrap()),
+        };
+        // Upgrades download several plugins at once, so their bars are drawn
+        // together rather than over each other.
+        let bar = bars().add(bar);
+        bar.set_message(format!("Downloading {name}"));
+        Self::Bar(bar)
+    }
+
+    pub fn advance(&mut self, bytes: u64) {
+        match self {
+            Self::Bar(bar) => bar.inc(bytes),
+            Self::Log {
+                name,
+                total,
+                downloaded,
+                last_logged,
+            } => {
+                *downloaded += bytes;
+                if last_logged.elapsed() >= LOG_INTERVAL {
+                    *last_logged = Instant::now();
+                    let downloaded = HumanBytes(*downloaded);
+                    match total {
+                        Some(total) => {
+                            eprintln!("Downloading {name}: {downloaded} of {}", HumanBytes(*total))
+                        }
+                        None => eprintln!("Downloading {name}: {downloaded}"),
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Drop for DownloadProgress {
+    fn drop(&mut self) {
+        // Clear the bar whether or not the download succeeded.
+        if let Self::Bar(bar) = self {
+            bar.finish_and_clear();
+        }
+    }
+}
+
+fn bars() -> &'static MultiProgress {
+    static BARS: OnceLock<MultiProgress> = OnceLock::new();
+    BARS.get_or_init(MultiProgress::new)
+}
@@ -0,0 +1,112 @@
+//! Verifies the Ed25519 signatures of plugin packages against locally trusted
+//! public keys.
+
+use std::path::Path;
+
+use anyhow::{anyhow, bail, Context, Result};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use ring::signature::{UnparsedPublicKey, ED25519};
+
+use crate::manifest::{PluginManifest, PluginPackage};
+
+/// Reads the trusted public keys from `path`: one base64-encoded key per line,
+/// ignoring blank lines and `#` comments. If the file doesn't exist, no keys
+/// are trusted.
+pub(crate) fn trusted_keys(path: &Path) -> Result<Vec<Vec<u8>>> {
+    let text = match std::fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+    };
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            STANDARD
+                .decode(line)
+                .with_context(|| format!("Invalid key '{line}' in {}", path.display()))
+        })
+        .collect()
+}
+
+/// A package signature, and the trusted key it should have been made with.
+pub(crate) struct Signed {
+    key: Vec<u8>,
+    signature: Vec<u8>,
+}
+
+/// Checks that `package` is signed, with a key in `trusted_keys`.
+pub(crate) fn signed_by_trusted_key(
+    manifest: &PluginManifest,
+    package: &PluginPackage,
+    trusted_keys: &[Vec<u8>],
+) -> Result<Signed> {
+    let name = manifest.name();
+    let (Some(signature), Some(key)) = (&package.signature, manifest.public_key()) else {
+        bail!("Plugin '{name}' is not signed");
+    };
+    let signature = STANDARD
+        .decode(signature)
+        .with_context(|| format!("Plugin '{name}' has an invalid signature"))?;
+    let key = STANDARD
+        .decode(key)
+        .with_context(|| format!("Plugin '{name}' has an invalid public key"))?;
+    if !trusted_keys.contains(&key) {
+        bail!("Plugin '{name}' is signed with a key that isn't trusted");
+    }
+    Ok(Signed { key, signature })
+}
+
+impl Signed {
+    /// Checks the signature against the contents of `package_file`.
+    pub(crate) fn verify(&self, package_file: &Path) -> Result<()> {
+        let contents = std::fs::read(package_file)
+            .with_context(|| format!("Failed to read {}", package_file.display()))?;
+        UnparsedPublicKey::new(&ED25519, &self.key)
+            .verify(&contents, &self.signature)
+            .map_err(|_| anyhow!("Signature does not match {}", package_file.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+    use ring::{
+        rand::SystemRandom,
+        signature::{Ed25519KeyPair, KeyPair},
+    };
+
+    #[test]
+    fn only_trusted_signatures_verify() -> Result<()> {
+        let pkcs8 = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new()).unwrap();
+        let key_pair = Ed25519KeyPair::from_pkcs8(pkcs8.as_ref()).unwrap();
+        let public_key = STANDARD.encode(key_pair.public_key());
+
+        let temp_dir = tempfile::tempdir()?;
+        let package_file = temp_dir.path().join("plugin.tar.gz");
+        std::fs::write(&package_file, "plugin")?;
+        let mut package = testing::package_json(
+            "linux",
+            "amd64",
+            "https://example.com/example.tar.gz",
+            "abc",
+        );
+        package["signature"] = STANDARD.encode(key_pair.sign(b"plugin")).into();
+        let mut manifest = testing::manifest_json("1.0.0", serde_json::json!([package]));
+        manifest["publicKey"] = public_key.clone().into();
+        let manifest: PluginManifest = serde_json::from_value(manifest)?;
+        let package = &manifest.packages[0];
+
+        let keys_file = temp_dir.path().join("trusted-keys");
+        assert!(signed_by_trusted_key(&manifest, package, &trusted_keys(&keys_file)?).is_err());
+
+        std::fs::write(&keys_file, format!("# release key\n{public_key}\n"))?;
+        let signed = signed_by_trusted_key(&manifest, package, &trusted_keys(&keys_file)?)?;
+        signed.verify(&package_file)?;
+
+        std::fs::write(&package_file, "tampered")?;
+        assert!(signed.verify(&package_file).is_err());
+        Ok(())
+    }
+}
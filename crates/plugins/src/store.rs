@@ -13,6 +13,15 @@ use crate::{error::*, manifest::PluginManifest};
 /// Directory where the manifests of installed plugins are stored.
 pub const PLUGIN_MANIFESTS_DIRECTORY_NAME: &str = "manifests";
 const INSTALLATION_RECORD_FILE_NAME: &str = ".install.json";
+/// Directory where installed plugins are moved while they are being replaced.
+const BACKUPS_DIRECTORY_NAME: &str = ".backups";
+/// File listing the public keys whose plugin signatures are trusted.
+const TRUSTED_KEYS_FILE_NAME: &str = ".trusted-keys";
+/// File recording the versions that plugins are pinned at.
+const PINS_FILE_NAME: &str = ".pins.json";
+/// File holding the URL of the plugins repository, if one other than the
+/// central repository has been chosen.
+const PLUGINS_REPO_URL_FILE_NAME: &str = ".plugins-repo-url";
 
 /// Houses utilities for getting the path to Spin plugin directories.
 pub struct PluginStore {
@@ -57,6 +66,23 @@ impl PluginStore {
         binary
     }
 
+    /// Get the path to the file of trusted public keys, which holds one
+    /// base64-encoded Ed25519 key per line.
+    pub fn trusted_keys_file(&self) -> PathBuf {
+        self.root.join(TRUSTED_KEYS_FILE_NAME)
+    }
+
+    /// Get the path to the file of plugin pins.
+    pub fn pins_file(&self) -> PathBuf {
+        self.root.join(PINS_FILE_NAME)
+    }
+
+    /// Get the path to the file holding the URL of the chosen plugins
+    /// repository.
+    pub fn plugins_repo_url_file(&self) -> PathBuf {
+        self.root.join(PLUGINS_REPO_URL_FILE_NAME)
+    }
+
     pub fn installation_record_file(&self, plugin_name: &str) -> PathBuf {
         self.root
             .join(plugin_name)
@@ -169,6 +195,68 @@ impl PluginStore {
         archive.unpack(&plugin_sub_dir)?;
         Ok(())
     }
+
+    /// Moves an installed plugin aside, so that it can be restored if
+    /// installing over it fails. Returns `None` if the plugin is not installed.
+    pub(crate) fn back_up_plugin(&self, plugin_name: &str) -> Result<Option<PluginBackup>> {
+        let plugin_sub_dir = self.plugin_subdirectory_path(plugin_name);
+        let manifest_path = self.installed_manifest_path(plugin_name);
+        if !plugin_sub_dir.exists() && !manifest_path.exists() {
+            return Ok(None);
+        }
+
+        let backup = PluginBackup {
+            plugin_name: plugin_name.to_owned(),
+            dir: self.root.join(BACKUPS_DIRECTORY_NAME).join(plugin_name),
+        };
+        fs::remove_dir_all(&backup.dir).ok();
+        fs::create_dir_all(&backup.dir)?;
+        move_if_exists(&plugin_sub_dir, &backup.plugin_sub_dir())?;
+        move_if_exists(&manifest_path, &backup.manifest_path())?;
+        Ok(Some(backup))
+    }
+}
+
+/// An installed plugin moved aside by [`PluginStore::back_up_plugin`].
+pub(crate) struct PluginBackup {
+    plugin_name: String,
+    dir: PathBuf,
+}
+
+impl PluginBackup {
+    fn plugin_sub_dir(&self) -> PathBuf {
+        self.dir.join("plugin")
+    }
+
+    fn manifest_path(&self) -> PathBuf {
+        self.dir.join(manifest_file_name(&self.plugin_name))
+    }
+
+    /// Puts the plugin back as it was, replacing whatever has been installed
+    /// since.
+    pub(crate) fn restore(self, store: &PluginStore) -> Result<()> {
+        let plugin_sub_dir = store.plugin_subdirectory_path(&self.plugin_name);
+        let manifest_path = store.installed_manifest_path(&self.plugin_name);
+        fs::remove_dir_all(&plugin_sub_dir).ok();
+        fs::remove_file(&manifest_path).ok();
+        move_if_exists(&self.plugin_sub_dir(), &plugin_sub_dir)?;
+        move_if_exists(&self.manifest_path(), &manifest_path)?;
+        self.discard();
+        Ok(())
+    }
+
+    /// Deletes the backup once the plugin has been replaced successfully.
+    pub(crate) fn discard(self) {
+        fs::remove_dir_all(&self.dir).ok();
+    }
+}
+
+fn move_if_exists(from: &Path, to: &Path) -> Result<()> {
+    if from.exists() {
+        fs::rename(from, to)
+            .with_context(|| format!("Failed to move {} to {}", from.display(), to.display()))?;
+    }
+    Ok(())
 }
 
 /// Given a plugin name, returns the expected file name for the installed manifest
@@ -0,0 +1,57 @@
+//! Fixtures shared by the tests in this crate.
+
+use std::path::Path;
+
+use serde_json::{json, Value};
+
+use crate::manifest::PluginManifest;
+
+/// The JSON manifest of version `version` of the `example` plugin, with
+/// `packages` as its packages.
+pub(crate) fn manifest_json(version: &str, packages: Value) -> Value {
+    json!({
+        "name": "example",
+        "version": version,
+        "spinCompatibility": ">= 0.1",
+        "license": "Apache-2.0",
+        "packages": packages,
+    })
+}
+
+pub(crate) fn manifest(version: &str, packages: Value) -> PluginManifest {
+    serde_json::from_value(manifest_json(version, packages)).unwrap()
+}
+
+/// The JSON of a package for `os`/`arch`, downloaded from `url`.
+pub(crate) fn package_json(os: &str, arch: &str, url: &str, sha256: &str) -> Value {
+    json!({
+        "os": os,
+        "arch": arch,
+        "url": url,
+        "sha256": sha256,
+    })
+}
+
+/// Runs `git` with `args` in `repo`, panicking if it fails.
+pub(crate) fn git(repo: &Path, args: &[&str]) {
+    let status = std::process::Command::new("git")
+        .arg("-C")
+        .arg(repo)
+        .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+        .args(args)
+        .output()
+        .unwrap()
+        .status;
+    assert!(status.success(), "git {args:?} failed");
+}
+
+/// Makes `repo` a Git repository on `branch`, committing everything in it.
+pub(crate) fn init_repo(repo: &Path, branch: &str) {
+    git(repo, &["init", "--initial-branch", branch]);
+    commit_all(repo, "init");
+}
+
+pub(crate) fn commit_all(repo: &Path, message: &str) {
+    git(repo, &["add", "."]);
+    git(repo, &["commit", "-m", message]);
+}
@@ -155,9 +155,10 @@ impl<Executor: TriggerExecutor> TriggerExecutorBuilder<Executor> {
 
                 self.loader.add_dynamic_host_component(
                     &mut builder,
-                    outbound_redis::OutboundRedisComponent {
-                        resolver: resolver_cell.clone(),
-                    },
+                    runtime_config::outbound_redis::build_component(
+                        &runtime_config,
+                        resolver_cell.clone(),
+                    ),
                 )?;
                 self.loader.add_dynamic_host_component(
                     &mut builder,
@@ -180,7 +181,7 @@ impl<Executor: TriggerExecutor> TriggerExecutorBuilder<Executor> {
                 self.loader.add_dynamic_host_component(
                     &mut builder,
                     runtime_config::llm::build_component(&runtime_config, init_data.llm.use_gpu)
-                        .await,
+                        .await?,
                 )?;
                 self.loader.add_dynamic_host_component(
                     &mut builder,
@@ -1,6 +1,7 @@
 pub mod client_tls;
 pub mod key_value;
 pub mod llm;
+pub mod outbound_redis;
 pub mod sqlite;
 pub mod variables_provider;
 
@@ -23,6 +24,7 @@ use self::{
     client_tls::{load_certs, load_key, ClientTlsOpts},
     key_value::{KeyValueStore, KeyValueStoreOpts},
     llm::LlmComputeOpts,
+    outbound_redis::OutboundRedisOpts,
     sqlite::SqliteDatabaseOpts,
     variables_provider::{VariablesProvider, VariablesProviderOpts},
 };
@@ -184,6 +186,16 @@ impl RuntimeConfig {
         }
     }
 
+    pub fn outbound_redis(&self) -> &OutboundRedisOpts {
+        const DEFAULT: &OutboundRedisOpts = &OutboundRedisOpts {
+            connect_timeout_ms: None,
+            command_timeout_ms: None,
+            db: None,
+        };
+        self.find_opt(|opts| &opts.outbound_redis)
+            .unwrap_or(DEFAULT)
+    }
+
     // returns the client tls options in form of nested
     // HashMap of { Component ID -> HashMap of { Host -> ParsedClientTlsOpts} }
     pub fn client_tls_opts(
@@ -259,6 +271,9 @@ pub struct RuntimeConfigOpts {
     #[serde(default)]
     pub llm_compute: Option<LlmComputeOpts>,
 
+    #[serde(default)]
+    pub outbound_redis: Option<OutboundRedisOpts>,
+
     #[serde(rename = "variables_provider", alias = "config_provider", default)]
     pub variables_providers: Vec<VariablesProviderOpts>,
 
@@ -756,6 +771,43 @@ ca_roots_file = "{}"
         assert!(component_no1_host_client_tls_opts.custom_root_ca.is_none());
     }
 
+    #[test]
+    fn outbound_redis_timeouts() -> Result<()> {
+        let mut config = RuntimeConfig::new(None);
+        assert_eq!(config.outbound_redis().connect_timeout_ms, None);
+        assert_eq!(config.outbound_redis().command_timeout_ms, None);
+
+        merge_config_toml(
+            &mut config,
+            toml! {
+                [outbound_redis]
+                connect_timeout_ms = 2500
+                command_timeout_ms = 500
+            },
+        );
+        assert_eq!(config.outbound_redis().connect_timeout_ms, Some(2500));
+        assert_eq!(config.outbound_redis().command_timeout_ms, Some(500));
+
+        Ok(())
+    }
+
+    #[test]
+    fn outbound_redis_database() -> Result<()> {
+        let mut config = RuntimeConfig::new(None);
+        assert_eq!(config.outbound_redis().db, None);
+
+        merge_config_toml(
+            &mut config,
+            toml! {
+                [outbound_redis]
+                db = 3
+            },
+        );
+        assert_eq!(config.outbound_redis().db, Some(3));
+
+        Ok(())
+    }
+
     fn merge_config_toml(config: &mut RuntimeConfig, value: toml::Value) {
         let data = toml::to_vec(&value).expect("encode toml");
         let mut file = NamedTempFile::new().expect("temp file");
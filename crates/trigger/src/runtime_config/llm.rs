@@ -1,4 +1,10 @@
-use spin_llm_remote_http::RemoteHttpLlmEngine;
+use std::{collections::HashMap, path::PathBuf, time::Duration};
+
+use anyhow::Context;
+use spin_llm_remote_http::{
+    ApiFlavor, AuthScheme, AuthSource, Certificate, Identity, ModelDefaults, RemoteHttpLlmEngine,
+    DEFAULT_RETRY_BACKOFF,
+};
 use url::Url;
 
 #[derive(Default)]
@@ -9,8 +15,8 @@ pub struct LLmOptions {
 pub(crate) async fn build_component(
     runtime_config: &crate::RuntimeConfig,
     use_gpu: bool,
-) -> spin_llm::LlmComponent {
-    match runtime_config.llm_compute() {
+) -> anyhow::Result<spin_llm::LlmComponent> {
+    let component = match runtime_config.llm_compute() {
         #[cfg(feature = "llm")]
         LlmComputeOpts::Spin => {
             let path = runtime_config
@@ -27,11 +33,25 @@ pub(crate) async fn build_component(
         }
         LlmComputeOpts::RemoteHttp(config) => {
             tracing::info!("Using remote compute for LLMs");
-            let engine =
-                RemoteHttpLlmEngine::new(config.url.to_owned(), config.auth_token.to_owned());
+            let mut engine = config
+                .build_engine()
+                .context("Failed to configure remote compute for LLMs")?;
+            if config.health_check {
+                engine
+                    .health()
+                    .await
+                    .map_err(|err| anyhow::anyhow!("{err:?}"))
+                    .context("LLM backend failed its health check")?;
+            }
+            for model in &config.warmup_models {
+                if let Err(err) = engine.warmup(model).await {
+                    tracing::warn!("Failed to warm up LLM model {model}: {err:?}");
+                }
+            }
             spin_llm::LlmComponent::new(move || Box::new(engine.clone()))
         }
-    }
+    };
+    Ok(component)
 }
 
 #[derive(Debug, serde::Deserialize)]
@@ -44,7 +64,215 @@ pub enum LlmComputeOpts {
 #[derive(Debug, serde::Deserialize)]
 pub struct RemoteHttpComputeOpts {
     url: Url,
+    /// Backend to use when `url` is unreachable or failing.
+    #[serde(default)]
+    fallback_url: Option<Url>,
+    #[serde(default)]
     auth_token: String,
+    /// Environment variable from which to read the auth token for each
+    /// request, instead of `auth_token`.
+    #[serde(default)]
+    auth_token_env: Option<String>,
+    /// File from which to read the auth token for each request, instead of
+    /// `auth_token`.
+    #[serde(default)]
+    auth_token_file: Option<PathBuf>,
+    /// How `auth_token` is sent: `bearer` (the default), `basic`, `none`, or
+    /// `{ api-key-header = "<header name>" }`.
+    #[serde(default)]
+    auth_scheme: AuthScheme,
+    /// The API spoken by the backend: `spin` (the default) or `openai`.
+    #[serde(default)]
+    api_flavor: ApiFlavor,
+    /// Sequences at which the backend should stop generating text.
+    #[serde(default)]
+    stop: Vec<String>,
+    /// Seed for the backend's sampler, for reproducible output.
+    #[serde(default)]
+    seed: Option<u64>,
+    /// Inferencing parameters to use for each model when a guest passes none.
+    #[serde(default)]
+    model_defaults: HashMap<String, ModelDefaults>,
+    /// OpenAI-style presence penalty sent with every inference request.
+    #[serde(default)]
+    presence_penalty: Option<f32>,
+    /// OpenAI-style frequency penalty sent with every inference request.
+    #[serde(default)]
+    frequency_penalty: Option<f32>,
+    /// Whether the backend accepts several prompts in one inference request.
+    #[serde(default)]
+    batching: bool,
+    /// Dimension that every embedding returned by the backend must have.
+    #[serde(default)]
+    embedding_dim: Option<usize>,
+    /// Whether to scale embeddings to unit length.
+    #[serde(default)]
+    normalize_embeddings: bool,
+    /// Number of recently embedded inputs whose embeddings are kept in memory
+    /// and reused instead of being requested again.
+    #[serde(default)]
+    embedding_cache_size: Option<usize>,
+    /// Maximum time, in seconds, to wait for each request to the backend.
+    #[serde(default)]
+    timeout_secs: Option<u64>,
+    /// Maximum time, in seconds, to wait to connect to the backend.
+    #[serde(default)]
+    connect_timeout_secs: Option<u64>,
+    /// Proxy through which to send requests to the backend.
+    #[serde(default)]
+    proxy: Option<String>,
+    /// `User-Agent` header to send to the backend.
+    #[serde(default)]
+    user_agent: Option<String>,
+    /// PEM file of additional root certificates to trust for the backend.
+    #[serde(default)]
+    ca_roots_file: Option<PathBuf>,
+    /// PEM file of the client certificate chain to present to the backend.
+    /// Must be given together with `private_key_file`.
+    #[serde(default)]
+    cert_chain_file: Option<PathBuf>,
+    /// PEM file of the PKCS#8 private key for `cert_chain_file`.
+    #[serde(default)]
+    private_key_file: Option<PathBuf>,
+    /// Accept any TLS certificate from the backend. Only for local testing.
+    #[serde(default)]
+    danger_accept_invalid_certs: bool,
+    /// Whether to send trace context headers to the backend. Defaults to true.
+    #[serde(default)]
+    propagate_trace_context: Option<bool>,
+    /// Whether to gzip-compress large request bodies.
+    #[serde(default)]
+    compress_requests: bool,
+    /// Path to which to send inferencing requests, if not the API's default.
+    #[serde(default)]
+    infer_path: Option<String>,
+    /// Path to which to send embeddings requests, if not the API's default.
+    #[serde(default)]
+    embed_path: Option<String>,
+    /// Check that the backend is healthy before starting.
+    #[serde(default)]
+    health_check: bool,
+    /// Path to query for the health check, if not `/health`.
+    #[serde(default)]
+    health_path: Option<String>,
+    /// Models to load on the backend at startup, so that the first request
+    /// for each doesn't wait for it to load.
+    #[serde(default)]
+    warmup_models: Vec<String>,
+    /// Number of times to retry a request after a connection failure or server error.
+    #[serde(default)]
+    max_retries: Option<u32>,
+    /// Number of consecutive failed requests after which requests stop being
+    /// sent to the backend for a while.
+    #[serde(default)]
+    circuit_breaker_threshold: Option<u32>,
+    /// Time, in seconds, for which requests stop being sent to a failing
+    /// backend. Defaults to 30.
+    #[serde(default)]
+    circuit_breaker_cooldown_secs: Option<u64>,
+    /// Maximum number of requests to send to the backend at once.
+    #[serde(default)]
+    max_concurrent_requests: Option<usize>,
+}
+
+impl RemoteHttpComputeOpts {
+    fn build_engine(&self) -> anyhow::Result<RemoteHttpLlmEngine> {
+        let mut engine = RemoteHttpLlmEngine::new(self.url.to_owned(), self.auth_token.to_owned())
+            .with_api_flavor(self.api_flavor)
+            .with_auth_scheme(self.auth_scheme.clone())
+            .with_stop_sequences(self.stop.iter().cloned())
+            .with_batching(self.batching)
+            .with_request_compression(self.compress_requests)
+            .with_embedding_normalization(self.normalize_embeddings);
+        match (&self.auth_token_env, &self.auth_token_file) {
+            (Some(name), None) => {
+                engine = engine.with_auth_source(AuthSource::Env(name.clone()));
+            }
+            (None, Some(path)) => {
+                engine = engine.with_auth_source(AuthSource::File(path.clone()));
+            }
+            (None, None) => {}
+            (Some(_), Some(_)) => {
+                anyhow::bail!("only one of auth_token_env and auth_token_file may be given")
+            }
+        }
+        if let Some(fallback_url) = &self.fallback_url {
+            engine = engine.with_fallback_endpoint(fallback_url.clone());
+        }
+        if let Some(seed) = self.seed {
+            engine = engine.with_seed(seed);
+        }
+        for (model, defaults) in &self.model_defaults {
+            engine = engine.with_model_defaults(model, defaults.clone());
+        }
+        if let Some(penalty) = self.presence_penalty {
+            engine = engine.with_presence_penalty(penalty);
+        }
+        if let Some(penalty) = self.frequency_penalty {
+            engine = engine.with_frequency_penalty(penalty);
+        }
+        if let Some(embedding_dim) = self.embedding_dim {
+            engine = engine.with_expected_embedding_dim(embedding_dim);
+        }
+        if let Some(capacity) = self.embedding_cache_size {
+            engine = engine.with_embedding_cache(capacity);
+        }
+        if let Some(timeout_secs) = self.timeout_secs {
+            engine = engine.with_timeout(Duration::from_secs(timeout_secs));
+        }
+        if let Some(connect_timeout_secs) = self.connect_timeout_secs {
+            engine = engine.with_connect_timeout(Duration::from_secs(connect_timeout_secs));
+        }
+        if let Some(proxy) = &self.proxy {
+            engine = engine.with_proxy(proxy);
+        }
+        if let Some(user_agent) = &self.user_agent {
+            engine = engine.with_user_agent(user_agent);
+        }
+        if let Some(propagate_trace_context) = self.propagate_trace_context {
+            engine = engine.with_trace_context_propagation(propagate_trace_context);
+        }
+        if let Some(infer_path) = &self.infer_path {
+            engine = engine.with_infer_path(infer_path);
+        }
+        if let Some(embed_path) = &self.embed_path {
+            engine = engine.with_embed_path(embed_path);
+        }
+        if let Some(health_path) = &self.health_path {
+            engine = engine.with_health_path(health_path);
+        }
+        if let Some(max_retries) = self.max_retries {
+            engine = engine.with_retries(max_retries, DEFAULT_RETRY_BACKOFF);
+        }
+        if let Some(threshold) = self.circuit_breaker_threshold {
+            let cooldown = Duration::from_secs(self.circuit_breaker_cooldown_secs.unwrap_or(30));
+            engine = engine.with_circuit_breaker(threshold, cooldown);
+        }
+        if let Some(max_concurrent_requests) = self.max_concurrent_requests {
+            engine = engine.with_concurrency_limit(max_concurrent_requests);
+        }
+        if let Some(path) = &self.ca_roots_file {
+            for cert in super::client_tls::load_certs(path).context("loading custom root ca")? {
+                engine = engine.with_root_certificate(Certificate::from_der(&cert)?);
+            }
+        }
+        match (&self.cert_chain_file, &self.private_key_file) {
+            (Some(cert_chain_file), Some(private_key_file)) => {
+                let cert_chain =
+                    std::fs::read(cert_chain_file).context("loading client tls certs")?;
+                let private_key = std::fs::read(private_key_file).context("loading private key")?;
+                let identity = Identity::from_pkcs8_pem(&cert_chain, &private_key)
+                    .context("invalid client tls certs or private key")?;
+                engine = engine.with_identity(identity);
+            }
+            (None, None) => {}
+            _ => anyhow::bail!("cert_chain_file and private_key_file must be given together"),
+        }
+        if self.danger_accept_invalid_certs {
+            engine = engine.danger_accept_invalid_certs();
+        }
+        Ok(engine)
+    }
 }
 
 #[cfg(not(feature = "llm"))]
@@ -0,0 +1,41 @@
+use std::time::Duration;
+
+use outbound_redis::OutboundRedisComponent;
+use serde::Deserialize;
+
+use crate::runtime_config::RuntimeConfig;
+
+/// Options for connections that components open to Redis servers.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OutboundRedisOpts {
+    /// How long to wait for a connection to be established, in milliseconds.
+    #[serde(default)]
+    pub connect_timeout_ms: Option<u64>,
+    /// How long to wait for the reply to each command, in milliseconds. By
+    /// default there is no limit, so that blocking commands such as `BLPOP`
+    /// can wait as long as they ask to.
+    #[serde(default)]
+    pub command_timeout_ms: Option<u64>,
+    /// The logical database to select on each connection, in place of the
+    /// one its address names. Redis Cluster only has database 0.
+    #[serde(default)]
+    pub db: Option<i64>,
+}
+
+pub(crate) fn build_component(
+    runtime_config: &RuntimeConfig,
+    resolver: spin_expressions::SharedPreparedResolver,
+) -> OutboundRedisComponent {
+    let opts = runtime_config.outbound_redis();
+    OutboundRedisComponent {
+        resolver,
+        credentials: None,
+        connect_timeout: opts
+            .connect_timeout_ms
+            .map(Duration::from_millis)
+            .unwrap_or(outbound_redis::DEFAULT_CONNECT_TIMEOUT),
+        command_timeout: opts.command_timeout_ms.map(Duration::from_millis),
+        db: opts.db,
+    }
+}
@@ -167,6 +167,29 @@ mod redis {
             }
         }
     }
+
+    impl From<v2::redis::RedisParameter> for v2_1::redis::RedisParameter {
+        fn from(value: v2::redis::RedisParameter) -> Self {
+            match value {
+                v2::redis::RedisParameter::Int64(i) => v2_1::redis::RedisParameter::Int64(i),
+                v2::redis::RedisParameter::Binary(b) => v2_1::redis::RedisParameter::Binary(b),
+            }
+        }
+    }
+
+    impl From<v2_1::redis::Error> for v2::redis::Error {
+        fn from(value: v2_1::redis::Error) -> Self {
+            match value {
+                v2_1::redis::Error::InvalidAddress => v2::redis::Error::InvalidAddress,
+                v2_1::redis::Error::TooManyConnections => v2::redis::Error::TooManyConnections,
+                v2_1::redis::Error::TypeError => v2::redis::Error::TypeError,
+                v2_1::redis::Error::AuthFailed => {
+                    v2::redis::Error::Other("authentication failed".into())
+                }
+                v2_1::redis::Error::Other(s) => v2::redis::Error::Other(s),
+            }
+        }
+    }
 }
 
 mod llm {
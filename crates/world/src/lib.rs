@@ -6,6 +6,7 @@ wasmtime::component::bindgen!({
     world host {
         include fermyon:spin/host;
         include fermyon:spin/platform@2.0.0;
+        import fermyon:spin/redis@2.1.0;
     }
     "#,
     path: "../../wit",
@@ -22,6 +23,7 @@ wasmtime::component::bindgen!({
         "fermyon:spin/rdbms-types@2.0.0/error" => v2::rdbms_types::Error,
         "fermyon:spin/redis-types/error" => v1::redis_types::Error,
         "fermyon:spin/redis@2.0.0/error" => v2::redis::Error,
+        "fermyon:spin/redis@2.1.0/error" => v2_1::redis::Error,
         "fermyon:spin/sqlite@2.0.0/error" => v2::sqlite::Error,
         "fermyon:spin/sqlite/error" => v1::sqlite::Error,
         "fermyon:spin/variables@2.0.0/error" => v2::variables::Error,
@@ -31,5 +33,6 @@ wasmtime::component::bindgen!({
 
 pub use fermyon::spin as v1;
 pub use fermyon::spin2_0_0 as v2;
+pub use fermyon::spin2_1_0 as v2_1;
 
 mod conversions;
@@ -8,7 +8,7 @@ use spin_cli::commands::{
     doctor::DoctorCommand,
     external::execute_external_subcommand,
     new::{AddCommand, NewCommand},
-    plugins::PluginCommands,
+    plugins::{self, NoChange, PluginCommands, EXIT_CODE_NO_CHANGE},
     registry::RegistryCommands,
     templates::TemplateCommands,
     up::UpCommand,
@@ -28,11 +28,14 @@ async fn main() {
             // exited unsuccessfully and thus already printed error messages. No need
             // to print anything additional.
             Some(e) => e.code(),
+            // Likewise, a plugin command with nothing to do has already said so.
+            None if err.is::<NoChange>() => EXIT_CODE_NO_CHANGE,
             // Otherwise we print the error chain.
             None => {
+                let code = plugins::failure_exit_code(&err);
                 terminal::error!("{err}");
                 print_error_chain(err);
-                1
+                code
             }
         };
 
@@ -203,12 +203,24 @@ fn offer_install(
 
 fn installer_for(plugin_name: &str) -> Install {
     Install {
-        name: Some(plugin_name.to_owned()),
+        name: vec![plugin_name.to_owned()],
         yes_to_all: true,
+        // The offer to install showed the license.
+        accept_license: true,
+        no_retry: false,
+        require_signed: false,
+        proxy: None,
         local_manifest_src: None,
-        remote_manifest_src: None,
+        remote_manifest_src: vec![],
+        git: None,
+        rev: None,
+        manifest_path: None,
         override_compatibility_check: false,
+        spin_version: None,
         version: None,
+        lock: false,
+        locked: false,
+        verbosity: Default::default(),
     }
 }
 
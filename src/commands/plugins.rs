@@ -2,13 +2,21 @@
 #![allow(clippy::almost_swapped)]
 
 use anyhow::{anyhow, Context, Result};
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
+use comfy_table::Table;
+use futures::StreamExt;
 use semver::Version;
+use serde::{Deserialize, Serialize};
 use spin_plugins::{
-    error::Error,
-    lookup::{fetch_plugins_repo, plugins_repo_url, PluginLookup},
+    error::{Error, IncompatibleError},
+    lockfile::{PluginLockfile, LOCKFILE_NAME},
+    lookup::{
+        configured_plugins_repo_url, fetch_plugins_repo, refresh_plugins_repo,
+        replace_plugins_repo, save_plugins_repo_url, PluginLookup,
+    },
     manager::{self, InstallAction, ManifestLocation, PluginManager},
     manifest::{PluginManifest, PluginPackage},
+    pins::PluginPins,
 };
 use std::path::{Path, PathBuf};
 use url::Url;
@@ -16,40 +24,121 @@ use url::Url;
 use crate::build_info::*;
 use crate::opts::*;
 
+/// Exit code of plugin commands that had nothing to do, such as installing a
+/// plugin that is already installed.
+pub const EXIT_CODE_NO_CHANGE: i32 = 3;
+/// Exit code of plugin commands that failed because a plugin doesn't support
+/// this version of Spin or this platform.
+pub const EXIT_CODE_INCOMPATIBLE: i32 = 4;
+
+/// How many plugins `spin plugins upgrade --all` and `spin plugins check-updates`
+/// look up, or download, at once.
+const UPGRADE_CONCURRENCY: usize = 4;
+
 /// Install/uninstall Spin plugins.
 #[derive(Subcommand, Debug)]
 pub enum PluginCommands {
+    /// List installed plugins that have newer versions, without upgrading them.
+    CheckUpdates(CheckUpdates),
+
+    /// Write the installed plugins and their versions to a file, for
+    /// `spin plugins import` to install elsewhere.
+    Export(Export),
+
+    /// Install the plugins listed in a file written by `spin plugins export`.
+    ///
+    /// Exits with code 3 if no plugin was installed because they already were
+    /// or installation was declined.
+    Import(Import),
+
     /// Install plugin from a manifest.
     ///
     /// The binary file and manifest of the plugin is copied to the local Spin
     /// plugins directory.
+    ///
+    /// Exits with code 3 if no plugin was installed because they already were
+    /// or installation was declined, and 4 if a plugin is incompatible.
     Install(Install),
 
     /// List available or installed plugins.
     List(List),
 
-    /// Search for plugins by name.
+    /// Hold a plugin at a version, so that `spin plugins upgrade --all` skips it.
+    Pin(Pin),
+
+    /// Search for plugins by name or description.
     Search(Search),
 
+    /// Show the details of a plugin in the spin-plugins repository.
+    Show(Show),
+
     /// Remove a plugin from your installation.
+    ///
+    /// Exits with code 3 if no plugin was uninstalled because none were present
+    /// or uninstallation was declined.
     Uninstall(Uninstall),
 
+    /// Remove the pin on a plugin, so that `spin plugins upgrade --all` upgrades it.
+    Unpin(Unpin),
+
     /// Upgrade one or all plugins.
+    ///
+    /// Exits with code 3 if no plugin was upgraded because they were up to
+    /// date or upgrading was declined, and 4 if a plugin is incompatible.
     Upgrade(Upgrade),
 
     /// Fetch the latest Spin plugins from the spin-plugins repository.
-    Update,
+    Update(Update),
+}
+
+/// Returned by plugin commands that had nothing to do, having already said
+/// so, to exit with `EXIT_CODE_NO_CHANGE`.
+#[derive(Debug)]
+pub struct NoChange;
+
+impl std::fmt::Display for NoChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("no changes were made")
+    }
+}
+
+impl std::error::Error for NoChange {}
+
+/// Fails with `NoChange` unless something `changed`.
+fn require_change(changed: bool) -> Result<()> {
+    if changed {
+        Ok(())
+    } else {
+        Err(NoChange.into())
+    }
+}
+
+/// The exit code for a plugin command that failed with `err`.
+pub fn failure_exit_code(err: &anyhow::Error) -> i32 {
+    if err.is::<NoChange>() {
+        EXIT_CODE_NO_CHANGE
+    } else if err.chain().any(|e| e.is::<IncompatibleError>()) {
+        EXIT_CODE_INCOMPATIBLE
+    } else {
+        1
+    }
 }
 
 impl PluginCommands {
     pub async fn run(self) -> Result<()> {
         match self {
+            PluginCommands::CheckUpdates(cmd) => cmd.run().await,
+            PluginCommands::Export(cmd) => cmd.run().await,
+            PluginCommands::Import(cmd) => cmd.run().await,
             PluginCommands::Install(cmd) => cmd.run().await,
             PluginCommands::List(cmd) => cmd.run().await,
+            PluginCommands::Pin(cmd) => cmd.run().await,
             PluginCommands::Search(cmd) => cmd.run().await,
+            PluginCommands::Show(cmd) => cmd.run().await,
             PluginCommands::Uninstall(cmd) => cmd.run().await,
+            PluginCommands::Unpin(cmd) => cmd.run().await,
             PluginCommands::Upgrade(cmd) => cmd.run().await,
-            PluginCommands::Update => update().await,
+            PluginCommands::Update(cmd) => cmd.run().await,
         }
     }
 }
@@ -57,14 +146,16 @@ impl PluginCommands {
 /// Install plugins from remote source
 #[derive(Parser, Debug)]
 pub struct Install {
-    /// Name of Spin plugin.
+    /// Names of Spin plugins.
     #[clap(
         name = PLUGIN_NAME_OPT,
         conflicts_with = PLUGIN_REMOTE_PLUGIN_MANIFEST_OPT,
         conflicts_with = PLUGIN_LOCAL_PLUGIN_MANIFEST_OPT,
-        required_unless_present_any = [PLUGIN_REMOTE_PLUGIN_MANIFEST_OPT, PLUGIN_LOCAL_PLUGIN_MANIFEST_OPT],
+        conflicts_with = PLUGIN_LOCKED_OPT,
+        conflicts_with = PLUGIN_GIT_OPT,
+        required_unless_present_any = [PLUGIN_REMOTE_PLUGIN_MANIFEST_OPT, PLUGIN_LOCAL_PLUGIN_MANIFEST_OPT, PLUGIN_LOCKED_OPT, PLUGIN_GIT_OPT],
     )]
-    pub name: Option<String>,
+    pub name: Vec<String>,
 
     /// Path to local plugin manifest.
     #[clap(
@@ -76,26 +167,74 @@ pub struct Install {
     )]
     pub local_manifest_src: Option<PathBuf>,
 
-    /// URL of remote plugin manifest to install.
+    /// URL of remote plugin manifest to install. May be given more than once.
     #[clap(
         name = PLUGIN_REMOTE_PLUGIN_MANIFEST_OPT,
         short = 'u',
         long = "url",
+        multiple_occurrences = true,
         conflicts_with = PLUGIN_LOCAL_PLUGIN_MANIFEST_OPT,
         conflicts_with = PLUGIN_NAME_OPT,
     )]
-    pub remote_manifest_src: Option<Url>,
+    pub remote_manifest_src: Vec<Url>,
+
+    /// URL of a Git repository containing the plugin manifest to install.
+    #[clap(
+        name = PLUGIN_GIT_OPT,
+        long = "git",
+        conflicts_with = PLUGIN_LOCAL_PLUGIN_MANIFEST_OPT,
+        conflicts_with = PLUGIN_REMOTE_PLUGIN_MANIFEST_OPT,
+        conflicts_with = PLUGIN_LOCKED_OPT,
+    )]
+    pub git: Option<Url>,
+
+    /// Branch or tag of the Git repository to install from, rather than its
+    /// default branch. Commit hashes aren't accepted.
+    #[clap(long = "rev", requires = PLUGIN_GIT_OPT)]
+    pub rev: Option<String>,
 
-    /// Skips prompt to accept the installation of the plugin.
+    /// Path of the plugin manifest within the Git repository. If omitted, the
+    /// repository root must contain exactly one plugin manifest.
+    #[clap(long = "manifest-path", requires = PLUGIN_GIT_OPT)]
+    pub manifest_path: Option<PathBuf>,
+
+    /// Skips prompt to accept the installation of the plugins. This accepts
+    /// their licenses too.
     #[clap(short = 'y', long = "yes", takes_value = false)]
     pub yes_to_all: bool,
 
+    /// Accepts the license of the plugin[s] without asking about it. The
+    /// accepted license, and when it was accepted, is recorded with each
+    /// installed plugin, as it is when the license is accepted at the prompt
+    /// or by `--yes`.
+    #[clap(long = "accept-license", takes_value = false)]
+    pub accept_license: bool,
+
+    /// Fail straight away if a download fails, rather than retrying it.
+    #[clap(long = "no-retry", takes_value = false)]
+    pub no_retry: bool,
+
+    /// Refuse plugins that aren't signed with a trusted key. Trusted keys are
+    /// listed in the .trusted-keys file in the plugins directory.
+    #[clap(long = "require-signed", takes_value = false)]
+    pub require_signed: bool,
+
+    /// Proxy to download plugins through. By default, the HTTPS_PROXY,
+    /// HTTP_PROXY and NO_PROXY environment variables are used.
+    #[clap(long = "proxy")]
+    pub proxy: Option<Url>,
+
     /// Overrides a failed compatibility check of the plugin with the current version of Spin.
     #[clap(long = PLUGIN_OVERRIDE_COMPATIBILITY_CHECK_FLAG, takes_value = false)]
     pub override_compatibility_check: bool,
 
+    /// Check compatibility with this version of Spin instead of the running
+    /// one, such as to see whether plugins will work with an upcoming release.
+    #[clap(long = "spin-version")]
+    pub spin_version: Option<Version>,
+
     /// Specific version of a plugin to be install from the centralized plugins
-    /// repository.
+    /// repository. Only one plugin may be named.
     #[clap(
         long = "version",
         short = 'v',
@@ -104,55 +243,200 @@ pub struct Install {
         requires(PLUGIN_NAME_OPT)
     )]
     pub version: Option<Version>,
+
+    /// Record the installed version in the spin-plugins.lock file in the
+    /// current directory, creating it if needed. Plugins installed from a
+    /// local file or a Git repository can't be locked.
+    #[clap(
+        long = "lock",
+        takes_value = false,
+        conflicts_with = PLUGIN_LOCKED_OPT,
+        conflicts_with = PLUGIN_LOCAL_PLUGIN_MANIFEST_OPT,
+        conflicts_with = PLUGIN_GIT_OPT,
+    )]
+    pub lock: bool,
+
+    /// Install the plugin versions recorded in the spin-plugins.lock file in
+    /// the current directory.
+    #[clap(
+        name = PLUGIN_LOCKED_OPT,
+        long = "locked",
+        takes_value = false,
+        conflicts_with = PLUGIN_REMOTE_PLUGIN_MANIFEST_OPT,
+        conflicts_with = PLUGIN_LOCAL_PLUGIN_MANIFEST_OPT,
+    )]
+    pub locked: bool,
+
+    #[clap(flatten)]
+    pub verbosity: Verbosity,
 }
 
 impl Install {
     pub async fn run(&self) -> Result<()> {
-        let manifest_location = match (&self.local_manifest_src, &self.remote_manifest_src, &self.name) {
-            (Some(path), None, None) => ManifestLocation::Local(path.to_path_buf()),
-            (None, Some(url), None) => ManifestLocation::Remote(url.clone()),
-            (None, None, Some(name)) => ManifestLocation::PluginsRepository(PluginLookup::new(name, self.version.clone())),
+        if self.locked {
+            return self.install_locked().await;
+        }
+        if let Some(url) = &self.git {
+            return self
+                .install_one(&ManifestLocation::Git {
+                    url: url.clone(),
+                    rev: self.rev.clone(),
+                    manifest_path: self.manifest_path.clone(),
+                })
+                .await
+                .and_then(require_change);
+        }
+        let manifest_locations = match (&self.local_manifest_src, self.remote_manifest_src.as_slice(), self.name.as_slice()) {
+            (Some(path), [], []) => vec![ManifestLocation::Local(path.to_path_buf())],
+            (None, urls @ [_, ..], []) => urls.iter().cloned().map(ManifestLocation::Remote).collect(),
+            (None, [], [name]) => vec![ManifestLocation::PluginsRepository(PluginLookup::new(name, self.version.clone()))],
+            (None, [], names @ [_, ..]) if self.version.is_none() => names.iter().map(|name| ManifestLocation::PluginsRepository(PluginLookup::new(name, None))).collect(),
+            (None, [], [_, ..]) => return Err(anyhow!("A version can only be given when installing a single plugin")),
             _ => return Err(anyhow::anyhow!("For plugin lookup, must provide exactly one of: plugin name, url to manifest, local path to manifest")),
         };
-        let manager = PluginManager::try_default()?;
+
+        if let [manifest_location] = manifest_locations.as_slice() {
+            return self
+                .install_one(manifest_location)
+                .await
+                .and_then(require_change);
+        }
+
+        // Install as many as possible, rather than stopping at the first failure.
+        let mut failures = Vec::new();
+        let mut changed = false;
+        for manifest_location in &manifest_locations {
+            match self.install_one(manifest_location).await {
+                Ok(installed) => changed |= installed,
+                Err(e) => {
+                    let description = describe_location(manifest_location);
+                    eprintln!("Failed to install {description}: {e:#}");
+                    failures.push(description);
+                }
+            }
+        }
+        let installed = manifest_locations.len() - failures.len();
+        if !self.verbosity.quiet {
+            println!(
+                "\n{installed} of {} plugins installed",
+                manifest_locations.len()
+            );
+        }
+        if !failures.is_empty() {
+            anyhow::bail!("Failed to install {}", failures.join(", "));
+        }
+        require_change(changed)
+    }
+
+    /// Installs the plugin at `manifest_location`, returning whether it was
+    /// installed rather than already installed or declined.
+    async fn install_one(&self, manifest_location: &ManifestLocation) -> Result<bool> {
+        let manager = plugin_manager(self.no_retry, self.require_signed, self.proxy.as_ref())?;
         // Downgrades are only allowed via the `upgrade` subcommand
         let downgrade = false;
-        let manifest = manager
-            .get_manifest(
-                &manifest_location,
-                self.override_compatibility_check,
-                SPIN_VERSION,
-            )
-            .await?;
-        try_install(
-            &manifest,
-            &manager,
-            self.yes_to_all,
-            self.override_compatibility_check,
+        let options = self.install_options(downgrade);
+        let manifest = get_manifest(&manager, manifest_location, &options).await?;
+        let installed = try_install(&manifest, &manager, &options, manifest_location).await?;
+        if self.lock {
+            let lockfile_path = Path::new(LOCKFILE_NAME);
+            let mut lockfile = PluginLockfile::load_or_default(lockfile_path)?;
+            lockfile.lock(&manifest, manifest_location)?;
+            lockfile.save(lockfile_path)?;
+            if !self.verbosity.quiet {
+                println!(
+                    "Locked plugin '{}' at version {} in {LOCKFILE_NAME}",
+                    manifest.name(),
+                    manifest.version()
+                );
+            }
+        }
+        Ok(installed)
+    }
+
+    fn install_options(&self, downgrade: bool) -> InstallOptions {
+        InstallOptions {
+            yes_to_all: self.yes_to_all,
+            accept_license: self.accept_license,
+            override_compatibility_check: self.override_compatibility_check,
             downgrade,
-            &manifest_location,
-        )
-        .await?;
+            spin_version: target_spin_version(self.spin_version.as_ref()),
+            verbosity: self.verbosity,
+        }
+    }
+
+    async fn install_locked(&self) -> Result<()> {
+        let lockfile = PluginLockfile::load(Path::new(LOCKFILE_NAME))?;
+        let manager = plugin_manager(self.no_retry, self.require_signed, self.proxy.as_ref())?;
+        // The locked version is installed even if a newer one is.
+        let downgrade = true;
+        let options = self.install_options(downgrade);
+        let mut changed = false;
+        for (name, locked) in lockfile.plugins() {
+            let manifest_location = locked.manifest_location(name)?;
+            let manifest = get_manifest(&manager, &manifest_location, &options).await?;
+            locked
+                .verify(&manifest)
+                .with_context(|| format!("Plugin '{name}' does not match {LOCKFILE_NAME}"))?;
+            changed |= try_install(&manifest, &manager, &options, &manifest_location).await?;
+        }
+        require_change(changed)
+    }
+}
+
+/// Hold a plugin at a version, so that `spin plugins upgrade --all` skips it.
+#[derive(Parser, Debug)]
+pub struct Pin {
+    /// Name of Spin plugin.
+    pub name: String,
+
+    /// Version to hold the plugin at.
+    pub version: Version,
+}
+
+impl Pin {
+    pub async fn run(self) -> Result<()> {
+        let manager = PluginManager::try_default()?;
+        let store = manager.store();
+        let mut pins = PluginPins::load(store)?;
+        pins.pin(&self.name, &self.version);
+        pins.save(store)?;
+        println!("Pinned plugin '{}' at version {}", self.name, self.version);
+
+        match store.read_plugin_manifest(&self.name) {
+            Ok(installed) if installed.version() != self.version.to_string() => {
+                terminal::warn!(
+                    "Plugin '{}' is installed at version {}. To switch to the pinned version, run `spin plugins upgrade {} -v {} --downgrade`.",
+                    self.name,
+                    installed.version(),
+                    self.name,
+                    self.version
+                );
+            }
+            Ok(_) => (),
+            Err(_) => terminal::warn!("Plugin '{}' is not installed", self.name),
+        }
         Ok(())
     }
 }
 
-/// Uninstalls specified plugin.
+/// Remove the pin on a plugin.
 #[derive(Parser, Debug)]
-pub struct Uninstall {
+pub struct Unpin {
     /// Name of Spin plugin.
     pub name: String,
 }
 
-impl Uninstall {
+impl Unpin {
     pub async fn run(self) -> Result<()> {
         let manager = PluginManager::try_default()?;
-        let uninstalled = manager.uninstall(&self.name)?;
-        if uninstalled {
-            println!("Plugin {} was successfully uninstalled", self.name);
+        let store = manager.store();
+        let mut pins = PluginPins::load(store)?;
+        if pins.unpin(&self.name) {
+            pins.save(store)?;
+            println!("Unpinned plugin '{}'", self.name);
         } else {
             println!(
-                "Plugin {} isn't present, so no changes were made",
+                "Plugin '{}' isn't pinned, so no changes were made",
                 self.name
             );
         }
@@ -160,6 +444,382 @@ impl Uninstall {
     }
 }
 
+/// Write the installed plugins and their versions to a file.
+#[derive(Parser, Debug)]
+pub struct Export {
+    /// File to write to. If omitted, the list is written to standard output.
+    #[clap(short = 'o', long = "output")]
+    pub output: Option<PathBuf>,
+
+    /// Record the URLs of plugins that were installed from a manifest URL, so
+    /// that they are installed from the same place.
+    #[clap(long = "include-urls", takes_value = false)]
+    pub include_urls: bool,
+}
+
+/// The plugins written by `spin plugins export`.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct PluginExport {
+    plugins: Vec<ExportedPlugin>,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct ExportedPlugin {
+    name: String,
+    version: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    manifest_url: Option<Url>,
+}
+
+impl ExportedPlugin {
+    fn manifest_location(&self) -> Result<ManifestLocation> {
+        match &self.manifest_url {
+            Some(url) => Ok(ManifestLocation::Remote(url.clone())),
+            None => {
+                let version = Version::parse(&self.version).with_context(|| {
+                    format!("Invalid version {} for {}", self.version, self.name)
+                })?;
+                Ok(ManifestLocation::PluginsRepository(PluginLookup::new(
+                    &self.name,
+                    Some(version),
+                )))
+            }
+        }
+    }
+}
+
+impl Export {
+    pub async fn run(self) -> Result<()> {
+        let manager = PluginManager::try_default()?;
+        let mut plugins: Vec<_> = manager
+            .store()
+            .installed_manifests()?
+            .into_iter()
+            .map(|manifest| ExportedPlugin {
+                manifest_url: self
+                    .include_urls
+                    .then(|| manager.installed_from_url(&manifest.name()))
+                    .flatten(),
+                name: manifest.name(),
+                version: manifest.version().to_owned(),
+            })
+            .collect();
+        plugins.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let text = serde_json::to_string_pretty(&PluginExport { plugins })?;
+        match &self.output {
+            Some(path) => std::fs::write(path, text)
+                .with_context(|| format!("Failed to write {}", path.display()))?,
+            None => println!("{text}"),
+        }
+        Ok(())
+    }
+}
+
+/// Install the plugins listed in a file written by `spin plugins export`.
+#[derive(Parser, Debug)]
+pub struct Import {
+    /// File listing the plugins to install.
+    pub file: PathBuf,
+
+    /// Skips prompt to accept the installation of the plugins. This accepts
+    /// their licenses too.
+    #[clap(short = 'y', long = "yes", takes_value = false)]
+    pub yes_to_all: bool,
+
+    /// Accepts the licenses of the plugins without asking about them. The
+    /// accepted license, and when it was accepted, is recorded with each
+    /// installed plugin, as it is when the license is accepted at the prompt
+    /// or by `--yes`.
+    #[clap(long = "accept-license", takes_value = false)]
+    pub accept_license: bool,
+
+    /// Fail straight away if a download fails, rather than retrying it.
+    #[clap(long = "no-retry", takes_value = false)]
+    pub no_retry: bool,
+
+    /// Refuse plugins that aren't signed with a trusted key. Trusted keys are
+    /// listed in the .trusted-keys file in the plugins directory.
+    #[clap(long = "require-signed", takes_value = false)]
+    pub require_signed: bool,
+
+    /// Proxy to download plugins through. By default, the HTTPS_PROXY,
+    /// HTTP_PROXY and NO_PROXY environment variables are used.
+    #[clap(long = "proxy")]
+    pub proxy: Option<Url>,
+
+    /// Overrides a failed compatibility check of the plugins with the current version of Spin.
+    #[clap(long = PLUGIN_OVERRIDE_COMPATIBILITY_CHECK_FLAG, takes_value = false)]
+    pub override_compatibility_check: bool,
+
+    /// Check compatibility with this version of Spin instead of the running
+    /// one, such as to see whether plugins will work with an upcoming release.
+    #[clap(long = "spin-version")]
+    pub spin_version: Option<Version>,
+
+    #[clap(flatten)]
+    pub verbosity: Verbosity,
+}
+
+impl Import {
+    pub async fn run(self) -> Result<()> {
+        let text = std::fs::read_to_string(&self.file)
+            .with_context(|| format!("Failed to read {}", self.file.display()))?;
+        let export: PluginExport = serde_json::from_str(&text)
+            .with_context(|| format!("Invalid plugin list {}", self.file.display()))?;
+        let manager = plugin_manager(self.no_retry, self.require_signed, self.proxy.as_ref())?;
+
+        // Install as many as possible, rather than stopping at the first failure.
+        let mut failures = vec![];
+        let mut changed = false;
+        for plugin in &export.plugins {
+            match self.import_one(&manager, plugin).await {
+                Ok(installed) => changed |= installed,
+                Err(e) => {
+                    eprintln!("Failed to install plugin '{}': {e:#}", plugin.name);
+                    failures.push(plugin.name.as_str());
+                }
+            }
+        }
+        if !failures.is_empty() {
+            anyhow::bail!("Failed to install {}", failures.join(", "));
+        }
+        require_change(changed)
+    }
+
+    /// Installs the listed `plugin`, returning whether it was installed rather
+    /// than already installed or declined.
+    async fn import_one(&self, manager: &PluginManager, plugin: &ExportedPlugin) -> Result<bool> {
+        let manifest_location = plugin.manifest_location()?;
+        let options = self.install_options();
+        let manifest = get_manifest(manager, &manifest_location, &options).await?;
+        try_install(&manifest, manager, &options, &manifest_location).await
+    }
+
+    fn install_options(&self) -> InstallOptions {
+        InstallOptions {
+            yes_to_all: self.yes_to_all,
+            accept_license: self.accept_license,
+            override_compatibility_check: self.override_compatibility_check,
+            // The listed version is installed even if a newer one is.
+            downgrade: true,
+            spin_version: target_spin_version(self.spin_version.as_ref()),
+            verbosity: self.verbosity,
+        }
+    }
+}
+
+/// Fetch the latest Spin plugins from the spin-plugins repository.
+#[derive(Parser, Debug)]
+pub struct Update {
+    /// Fetch plugins from this repository instead, such as an internal mirror
+    /// of spin-plugins. It is used by all plugin commands until another
+    /// repository is given.
+    #[clap(long = "repo-url")]
+    pub repo_url: Option<Url>,
+
+    /// Clone the plugins repository again in full, rather than pulling only
+    /// what changed since the last update.
+    #[clap(long = "force", takes_value = false)]
+    pub force: bool,
+
+    /// Proxy to fetch the plugins repository through. By default, Git's own
+    /// proxy settings are used.
+    #[clap(long = "proxy")]
+    pub proxy: Option<Url>,
+
+    #[clap(flatten)]
+    pub verbosity: Verbosity,
+}
+
+impl Update {
+    pub async fn run(self) -> Result<()> {
+        self.verbosity.detail(match (&self.repo_url, self.force) {
+            (Some(url), _) => format!("Replacing the plugins repository with a clone of {url}"),
+            (None, true) => "Cloning the plugins repository again".to_owned(),
+            (None, false) => "Pulling changes to the plugins repository".to_owned(),
+        });
+        update_silent_from(self.repo_url.as_ref(), self.force, self.proxy.as_ref()).await?;
+        if !self.verbosity.quiet {
+            println!("Plugin information updated successfully");
+        }
+        Ok(())
+    }
+}
+
+/// How much plugin commands print about what they are doing.
+#[derive(Parser, Debug, Clone, Copy, Default)]
+pub struct Verbosity {
+    /// Print only errors and prompts, not progress or success messages.
+    #[clap(long = "quiet", takes_value = false, conflicts_with = "verbose")]
+    pub quiet: bool,
+
+    /// Also print how plugins are resolved, such as which package is chosen and
+    /// why a plugin is considered compatible.
+    #[clap(long = "verbose", takes_value = false)]
+    pub verbose: bool,
+}
+
+impl Verbosity {
+    /// Prints a detail of what is being done, if `--verbose` was given.
+    fn detail(&self, message: impl std::fmt::Display) {
+        if self.verbose {
+            eprintln!("{message}");
+        }
+    }
+}
+
+/// How a plugin is to be installed, as chosen on the command line.
+struct InstallOptions {
+    yes_to_all: bool,
+    accept_license: bool,
+    override_compatibility_check: bool,
+    downgrade: bool,
+    /// The version of Spin to check the plugin's compatibility with.
+    spin_version: String,
+    verbosity: Verbosity,
+}
+
+/// The version of Spin to check plugins' compatibility with: `spin_version` if
+/// given, otherwise this one.
+fn target_spin_version(spin_version: Option<&Version>) -> String {
+    spin_version.map_or_else(|| SPIN_VERSION.to_owned(), Version::to_string)
+}
+
+/// Creates a plugin manager, which retries failed downloads unless `no_retry`
+/// is set.
+fn plugin_manager(
+    no_retry: bool,
+    require_signed: bool,
+    proxy: Option<&Url>,
+) -> Result<PluginManager> {
+    let mut manager = PluginManager::try_default()?.with_required_signatures(require_signed);
+    if let Some(proxy) = proxy {
+        manager = manager.with_proxy(proxy.clone())?;
+    }
+    if no_retry {
+        Ok(manager.with_download_retries(0))
+    } else {
+        Ok(manager)
+    }
+}
+
+/// Describes where a plugin is being installed from, for reporting failures.
+fn describe_location(manifest_location: &ManifestLocation) -> String {
+    match manifest_location {
+        ManifestLocation::Local(path) => format!("plugin from {}", path.display()),
+        ManifestLocation::Remote(url) => format!("plugin from {url}"),
+        ManifestLocation::PluginsRepository(lookup) => format!("plugin '{}'", lookup.name),
+        ManifestLocation::Git { url, .. } => format!("plugin from {url}"),
+    }
+}
+
+/// Uninstalls specified plugins.
+#[derive(Parser, Debug)]
+pub struct Uninstall {
+    /// Names of Spin plugins.
+    #[clap(
+        name = PLUGIN_NAME_OPT,
+        conflicts_with = PLUGIN_ALL_OPT,
+        required_unless_present = PLUGIN_ALL_OPT,
+    )]
+    pub name: Vec<String>,
+
+    /// Uninstall all plugins.
+    #[clap(
+        short = 'a',
+        long = "all",
+        name = PLUGIN_ALL_OPT,
+        conflicts_with = PLUGIN_NAME_OPT,
+        takes_value = false,
+    )]
+    pub all: bool,
+
+    /// Skips prompt to confirm uninstalling all plugins.
+    #[clap(short = 'y', long = "yes", takes_value = false)]
+    pub yes_to_all: bool,
+
+    #[clap(flatten)]
+    pub verbosity: Verbosity,
+}
+
+impl Uninstall {
+    pub async fn run(self) -> Result<()> {
+        let manager = PluginManager::try_default()?;
+        let names = if self.all {
+            let mut names: Vec<_> = manager
+                .store()
+                .installed_manifests()?
+                .iter()
+                .map(|m| m.name())
+                .collect();
+            names.sort();
+            if names.is_empty() {
+                if !self.verbosity.quiet {
+                    println!("No plugins are installed, so no changes were made");
+                }
+                return Err(NoChange.into());
+            }
+            if !self.yes_to_all && !prompt_confirm_uninstall_all(&names)? {
+                return Err(NoChange.into());
+            }
+            names
+        } else {
+            self.name
+        };
+
+        if let [name] = names.as_slice() {
+            return uninstall_one(&manager, name, self.verbosity).and_then(require_change);
+        }
+
+        let mut failures = vec![];
+        let mut changed = false;
+        for name in &names {
+            match uninstall_one(&manager, name, self.verbosity) {
+                Ok(uninstalled) => changed |= uninstalled,
+                Err(e) => {
+                    eprintln!("Failed to uninstall plugin {name}: {e:#}");
+                    failures.push(name.as_str());
+                }
+            }
+        }
+        if !failures.is_empty() {
+            anyhow::bail!("Failed to uninstall {}", failures.join(", "));
+        }
+        require_change(changed)
+    }
+}
+
+/// Uninstalls the plugin named `name`, returning whether it was present.
+fn uninstall_one(manager: &PluginManager, name: &str, verbosity: Verbosity) -> Result<bool> {
+    let uninstalled = manager.uninstall(name)?;
+    if !verbosity.quiet {
+        if uninstalled {
+            println!("Plugin {name} was successfully uninstalled");
+        } else {
+            println!("Plugin {name} isn't present, so no changes were made");
+        }
+    }
+    Ok(uninstalled)
+}
+
+fn prompt_confirm_uninstall_all(names: &[String]) -> Result<bool> {
+    println!(
+        "The following plugins will be uninstalled: {}",
+        names.join(", ")
+    );
+    let uninstall = dialoguer::Confirm::new()
+        .with_prompt("Are you sure you want to continue?")
+        .default(false)
+        .interact_opt()?
+        .unwrap_or(false);
+    if !uninstall {
+        println!("No plugins were uninstalled");
+    }
+    Ok(uninstall)
+}
+
 #[derive(Parser, Debug)]
 pub struct Upgrade {
     /// Name of Spin plugin to upgrade.
@@ -199,14 +859,41 @@ pub struct Upgrade {
     )]
     pub remote_manifest_src: Option<Url>,
 
-    /// Skips prompt to accept the installation of the plugin[s].
+    /// Skips prompt to accept the installation of the plugin[s]. This accepts
+    /// their licenses too.
     #[clap(short = 'y', long = "yes", takes_value = false)]
     pub yes_to_all: bool,
 
+    /// Accepts the license of the plugin[s] without asking about it. The
+    /// accepted license, and when it was accepted, is recorded with each
+    /// installed plugin, as it is when the license is accepted at the prompt
+    /// or by `--yes`.
+    #[clap(long = "accept-license", takes_value = false)]
+    pub accept_license: bool,
+
+    /// Fail straight away if a download fails, rather than retrying it.
+    #[clap(long = "no-retry", takes_value = false)]
+    pub no_retry: bool,
+
+    /// Refuse plugins that aren't signed with a trusted key. Trusted keys are
+    /// listed in the .trusted-keys file in the plugins directory.
+    #[clap(long = "require-signed", takes_value = false)]
+    pub require_signed: bool,
+
+    /// Proxy to download plugins through. By default, the HTTPS_PROXY,
+    /// HTTP_PROXY and NO_PROXY environment variables are used.
+    #[clap(long = "proxy")]
+    pub proxy: Option<Url>,
+
     /// Overrides a failed compatibility check of the plugin with the current version of Spin.
     #[clap(long = PLUGIN_OVERRIDE_COMPATIBILITY_CHECK_FLAG, takes_value = false)]
     pub override_compatibility_check: bool,
 
+    /// Check compatibility with this version of Spin instead of the running
+    /// one, such as to see whether plugins will work with an upcoming release.
+    #[clap(long = "spin-version")]
+    pub spin_version: Option<Version>,
+
     /// Specific version of a plugin to be install from the centralized plugins
     /// repository.
     #[clap(
@@ -222,6 +909,9 @@ pub struct Upgrade {
     /// Allow downgrading a plugin's version.
     #[clap(short = 'd', long = "downgrade", takes_value = false)]
     pub downgrade: bool,
+
+    #[clap(flatten)]
+    pub verbosity: Verbosity,
 }
 
 impl Upgrade {
@@ -236,8 +926,10 @@ impl Upgrade {
 
         // Check if no plugins are currently installed
         if !manifests_dir.exists() {
-            println!("No currently installed plugins to upgrade.");
-            return Ok(());
+            if !self.verbosity.quiet {
+                println!("No currently installed plugins to upgrade.");
+            }
+            return Err(NoChange.into());
         }
 
         if self.all {
@@ -268,13 +960,16 @@ impl Upgrade {
             .collect();
 
         if installed_in_catalogue.is_empty() {
-            eprintln!("No plugins found to upgrade");
-            return Ok(());
+            if !self.verbosity.quiet {
+                eprintln!("No plugins found to upgrade");
+            }
+            return Err(NoChange.into());
         }
 
         let mut eligible_plugins = Vec::new();
 
         // Getting only eligible plugins to upgrade
+        let spin_version = target_spin_version(self.spin_version.as_ref());
         for installed_plugin in installed_in_catalogue {
             let manager = PluginManager::try_default()?;
             let manifest_location = ManifestLocation::PluginsRepository(PluginLookup::new(
@@ -284,13 +979,13 @@ impl Upgrade {
 
             // Attempt to get the manifest to check eligibility to upgrade
             if let Ok(manifest) = manager
-                .get_manifest(&manifest_location, false, SPIN_VERSION)
+                .get_manifest(&manifest_location, false, &spin_version)
                 .await
             {
                 // Check if upgraded candidates have a newer version and if are compatible
                 if is_potential_upgrade(&installed_plugin.manifest, &manifest)
                     && PluginCompatibility::Compatible
-                        == PluginCompatibility::for_current(&manifest)
+                        == PluginCompatibility::for_version(&manifest, &spin_version)
                 {
                     eligible_plugins.push((installed_plugin, manifest));
                 }
@@ -298,8 +993,10 @@ impl Upgrade {
         }
 
         if eligible_plugins.is_empty() {
-            eprintln!("All plugins are up to date");
-            return Ok(());
+            if !self.verbosity.quiet {
+                eprintln!("All plugins are up to date");
+            }
+            return Err(NoChange.into());
         }
 
         let names: Vec<_> = eligible_plugins
@@ -319,33 +1016,45 @@ impl Upgrade {
         );
         let selected_indexes = match dialoguer::MultiSelect::new().items(&names).interact_opt()? {
             Some(indexes) => indexes,
-            None => return Ok(()),
+            None => return Err(NoChange.into()),
         };
 
         let plugins_selected = elements_at(eligible_plugins, selected_indexes);
 
         if plugins_selected.is_empty() {
-            eprintln!("No plugins selected");
-            return Ok(());
+            if !self.verbosity.quiet {
+                eprintln!("No plugins selected");
+            }
+            return Err(NoChange.into());
         }
 
         // Upgrade plugins selected
+        let mut changed = false;
         for (installed_plugin, manifest) in plugins_selected {
-            let manager = PluginManager::try_default()?;
+            let manager = plugin_manager(self.no_retry, self.require_signed, self.proxy.as_ref())?;
             let manifest_location = ManifestLocation::PluginsRepository(PluginLookup::new(
                 &installed_plugin.name,
                 None,
             ));
 
-            try_install(&manifest, &manager, true, false, false, &manifest_location).await?;
+            let options = InstallOptions {
+                yes_to_all: true,
+                override_compatibility_check: false,
+                downgrade: false,
+                ..self.install_options()
+            };
+            changed |= try_install(&manifest, &manager, &options, &manifest_location).await?;
         }
 
-        Ok(())
+        require_change(changed)
     }
 
-    // Install the latest of all currently installed plugins
+    // Install the latest of all currently installed plugins. Manifests are
+    // looked up and packages downloaded concurrently, but prompts and output
+    // are in plugin name order.
     async fn upgrade_all(&self, manifests_dir: impl AsRef<Path>) -> Result<()> {
-        let manager = PluginManager::try_default()?;
+        let manager = plugin_manager(self.no_retry, self.require_signed, self.proxy.as_ref())?;
+        let mut names = Vec::new();
         for plugin in std::fs::read_dir(manifests_dir)? {
             let path = plugin?.path();
             let name = path
@@ -354,16 +1063,45 @@ impl Upgrade {
                 .to_str()
                 .ok_or_else(|| anyhow!("Cannot convert path {} stem to str", path.display()))?
                 .to_string();
-            let manifest_location =
-                ManifestLocation::PluginsRepository(PluginLookup::new(&name, None));
-            let manifest = match manager
-                .get_manifest(
-                    &manifest_location,
-                    self.override_compatibility_check,
-                    SPIN_VERSION,
-                )
-                .await
-            {
+            names.push(name);
+        }
+        names.sort();
+
+        let pins = PluginPins::load(manager.store())?;
+        names.retain(|name| match pins.pinned_version(name) {
+            Some(version) => {
+                if !self.verbosity.quiet {
+                    println!("Skipping plugin '{name}', which is pinned at version {version}");
+                }
+                false
+            }
+            None => true,
+        });
+
+        let options = self.install_options();
+        let lookups: Vec<_> = futures::stream::iter(names)
+            .map(|name| async {
+                let manifest_location =
+                    ManifestLocation::PluginsRepository(PluginLookup::new(&name, None));
+                self.verbosity.detail(format!(
+                    "Looking up the latest version of plugin '{name}' in the plugins repository"
+                ));
+                let manifest = manager
+                    .get_manifest(
+                        &manifest_location,
+                        self.override_compatibility_check,
+                        &options.spin_version,
+                    )
+                    .await;
+                (name, manifest_location, manifest)
+            })
+            .buffered(UPGRADE_CONCURRENCY)
+            .collect()
+            .await;
+
+        let mut upgrades = Vec::new();
+        for (name, manifest_location, manifest) in lookups {
+            let manifest = match manifest {
                 Err(Error::NotFound(e)) => {
                     tracing::info!("Could not upgrade plugin '{name}': {e:?}");
                     continue;
@@ -371,51 +1109,186 @@ impl Upgrade {
                 Err(e) => return Err(e.into()),
                 Ok(m) => m,
             };
-            try_install(
-                &manifest,
-                &manager,
-                self.yes_to_all,
-                self.override_compatibility_check,
-                self.downgrade,
-                &manifest_location,
-            )
-            .await?;
+            upgrades.push((manifest, manifest_location));
         }
-        Ok(())
+
+        // Ask about every upgrade before starting any, so that the prompts
+        // aren't interleaved with downloads.
+        let mut confirmed = Vec::new();
+        for (manifest, manifest_location) in &upgrades {
+            if let Some(package) = confirm_install(manifest, &manager, &options)? {
+                confirmed.push((manifest, package, manifest_location));
+            }
+        }
+
+        let manager = &manager;
+        let installed: Vec<_> = futures::stream::iter(confirmed)
+            .map(|(manifest, package, manifest_location)| async move {
+                let installed = manager.install(manifest, package, manifest_location).await;
+                (manifest, installed)
+            })
+            .buffered(UPGRADE_CONCURRENCY)
+            .collect()
+            .await;
+
+        let mut first_error = None;
+        let mut changed = false;
+        for (manifest, installed) in installed {
+            match installed {
+                Ok(installed) => {
+                    report_installed(&installed, manifest, self.verbosity);
+                    changed = true;
+                }
+                Err(e) => {
+                    let e = e.context(format!("Failed to upgrade plugin '{}'", manifest.name()));
+                    if first_error.is_none() {
+                        first_error = Some(e);
+                    } else {
+                        eprintln!("{e:#}");
+                    }
+                }
+            }
+        }
+        first_error.map_or(require_change(changed), Err)
     }
 
     async fn upgrade_one(self) -> Result<()> {
-        let manager = PluginManager::try_default()?;
-        let manifest_location = match (self.local_manifest_src, self.remote_manifest_src) {
-            (Some(path), None) => ManifestLocation::Local(path),
-            (None, Some(url)) => ManifestLocation::Remote(url),
+        let manager = plugin_manager(self.no_retry, self.require_signed, self.proxy.as_ref())?;
+        let manifest_location = match (&self.local_manifest_src, &self.remote_manifest_src) {
+            (Some(path), None) => ManifestLocation::Local(path.clone()),
+            (None, Some(url)) => ManifestLocation::Remote(url.clone()),
             _ => ManifestLocation::PluginsRepository(PluginLookup::new(
                 self.name
                     .as_ref()
                     .context("plugin name is required for upgrades")?,
-                self.version,
+                self.version.clone(),
             )),
         };
-        let manifest = manager
-            .get_manifest(
-                &manifest_location,
-                self.override_compatibility_check,
-                SPIN_VERSION,
-            )
-            .await?;
-        try_install(
-            &manifest,
-            &manager,
-            self.yes_to_all,
-            self.override_compatibility_check,
-            self.downgrade,
-            &manifest_location,
-        )
-        .await?;
+        let options = self.install_options();
+        let manifest = get_manifest(&manager, &manifest_location, &options).await?;
+        let installed = try_install(&manifest, &manager, &options, &manifest_location).await?;
+        require_change(installed)
+    }
+
+    fn install_options(&self) -> InstallOptions {
+        InstallOptions {
+            yes_to_all: self.yes_to_all,
+            accept_license: self.accept_license,
+            override_compatibility_check: self.override_compatibility_check,
+            downgrade: self.downgrade,
+            spin_version: target_spin_version(self.spin_version.as_ref()),
+            verbosity: self.verbosity,
+        }
+    }
+}
+
+/// Lists installed plugins that have newer versions, without upgrading them.
+#[derive(Parser, Debug)]
+pub struct CheckUpdates {
+    /// Look for the latest versions compatible with this version of Spin
+    /// instead of the running one, as `upgrade --spin-version` would.
+    #[clap(long = "spin-version")]
+    pub spin_version: Option<Version>,
+}
+
+impl CheckUpdates {
+    pub async fn run(self) -> Result<()> {
+        if update_silent().await.is_err() {
+            terminal::warn!("Couldn't update plugins registry cache - using most recent");
+        }
+
+        let manager = PluginManager::try_default()?;
+        let mut installed = manager.store().installed_manifests()?;
+        if installed.is_empty() {
+            println!("No currently installed plugins to check.");
+            return Ok(());
+        }
+        installed.sort_by_key(|m| m.name());
+
+        // Look up the versions that `spin plugins upgrade --all` would install.
+        let spin_version = target_spin_version(self.spin_version.as_ref());
+        let (manager, spin_version) = (&manager, spin_version.as_str());
+        let lookups: Vec<_> = futures::stream::iter(installed)
+            .map(|current| async move {
+                let location =
+                    ManifestLocation::PluginsRepository(PluginLookup::new(&current.name(), None));
+                let latest = manager.get_manifest(&location, false, spin_version).await;
+                (current, latest)
+            })
+            .buffered(UPGRADE_CONCURRENCY)
+            .collect()
+            .await;
+
+        let mut table = Table::new();
+        table.set_header(vec!["Name", "Installed", "Latest", "Status"]);
+        table.load_preset(comfy_table::presets::ASCII_BORDERS_ONLY_CONDENSED);
+        let (mut outdated, mut unknown) = (0, 0);
+        for (current, latest) in lookups {
+            if let Err(e) = &latest {
+                if !matches!(e, Error::NotFound(_)) {
+                    terminal::warn!("Couldn't look up plugin '{}': {e}", current.name());
+                }
+            }
+            let (latest, status) = latest_and_status(&current, &latest);
+            match status {
+                UPDATE_AVAILABLE => outdated += 1,
+                UNKNOWN => unknown += 1,
+                _ => (),
+            }
+            table.add_row(vec![
+                current.name(),
+                current.version().to_owned(),
+                latest,
+                status.to_owned(),
+            ]);
+        }
+
+        println!("{table}");
+        if outdated > 0 {
+            println!("\n{outdated} plugin(s) can be upgraded with `spin plugins upgrade`");
+        } else if unknown == 0 {
+            println!("\nAll plugins are up to date");
+        }
+        if unknown > 0 {
+            println!("{unknown} plugin(s) couldn't be checked");
+        }
         Ok(())
     }
 }
 
+const UPDATE_AVAILABLE: &str = "update available";
+const UNKNOWN: &str = "unknown";
+
+/// The latest version of an installed plugin, from looking it up in the
+/// plugins repository, and how the installed version compares to it. Both are
+/// unknown if the lookup failed other than by not finding the plugin.
+fn latest_and_status(
+    current: &PluginManifest,
+    latest: &Result<PluginManifest, Error>,
+) -> (String, &'static str) {
+    match latest {
+        Ok(latest) => (
+            latest.version().to_owned(),
+            update_status(current, Some(latest)),
+        ),
+        Err(Error::NotFound(e)) => {
+            tracing::info!("Could not look up plugin '{}': {e:?}", current.name());
+            ("-".to_owned(), update_status(current, None))
+        }
+        Err(_) => (UNKNOWN.to_owned(), UNKNOWN),
+    }
+}
+
+/// Describes how an installed plugin compares to the latest version of it in
+/// the plugins repository, if it's there at all.
+fn update_status(current: &PluginManifest, latest: Option<&PluginManifest>) -> &'static str {
+    match latest {
+        None => "not in repository",
+        Some(latest) if is_potential_upgrade(current, latest) => UPDATE_AVAILABLE,
+        Some(_) => "up to date",
+    }
+}
+
 fn is_potential_upgrade(current: &PluginManifest, candidate: &PluginManifest) -> bool {
     match (current.try_version(), candidate.try_version()) {
         (Ok(cur_ver), Ok(cand_ver)) => cand_ver > cur_ver,
@@ -534,8 +1407,13 @@ fn latest_and_rest(
 /// List available or installed plugins.
 #[derive(Parser, Debug)]
 pub struct List {
-    /// List only installed plugins.
-    #[clap(long = "installed", takes_value = false, group = "which")]
+    /// List only installed plugins, without checking the catalogue for newer versions.
+    #[clap(
+        long = "installed",
+        alias = "installed-only",
+        takes_value = false,
+        group = "which"
+    )]
     pub installed: bool,
 
     /// List all versions of plugins. This is the default behaviour.
@@ -549,6 +1427,31 @@ pub struct List {
     /// Filter the list to plugins containing this string.
     #[clap(long = "filter")]
     pub filter: Option<String>,
+
+    /// The format in which to list the plugins.
+    #[clap(value_enum, long = "format", default_value = "plain")]
+    pub format: OutputFormat,
+
+    /// Print JSON, as a shorthand for `--format json`.
+    #[clap(long = "json", takes_value = false, conflicts_with = "format")]
+    pub json: bool,
+}
+
+#[derive(ValueEnum, Clone, Debug)]
+pub enum OutputFormat {
+    Plain,
+    Json,
+}
+
+impl OutputFormat {
+    /// The format given by `--format`, unless `--json` asked for JSON.
+    fn chosen(format: &Self, json: bool) -> &Self {
+        if json {
+            &Self::Json
+        } else {
+            format
+        }
+    }
 }
 
 impl List {
@@ -569,11 +1472,18 @@ impl List {
             plugins.retain(|p| p.name.contains(filter));
         }
 
-        Self::print(&plugins);
+        Self::print(&plugins, OutputFormat::chosen(&self.format, self.json))
+    }
+
+    fn print(plugins: &[PluginDescriptor], format: &OutputFormat) -> Result<()> {
+        match format {
+            OutputFormat::Plain => Self::print_plain(plugins),
+            OutputFormat::Json => Self::print_json(plugins)?,
+        }
         Ok(())
     }
 
-    fn print(plugins: &[PluginDescriptor]) {
+    fn print_plain(plugins: &[PluginDescriptor]) {
         if plugins.is_empty() {
             println!("No plugins found");
         } else {
@@ -596,28 +1506,180 @@ impl List {
             }
         }
     }
+
+    fn print_json(plugins: &[PluginDescriptor]) -> Result<()> {
+        let json_vals: Vec<_> = plugins.iter().map(json_list_format).collect();
+        let json_text = serde_json::to_string_pretty(&json_vals)?;
+        println!("{}", json_text);
+        Ok(())
+    }
+}
+
+fn json_list_format(plugin: &PluginDescriptor) -> PluginListJson {
+    PluginListJson {
+        name: plugin.name.clone(),
+        version: plugin.version.clone(),
+        description: plugin.manifest.description().map(|d| d.to_owned()),
+        installed: plugin.installed,
+        installed_version: plugin.installed_version.clone(),
+        compatible: plugin.compatibility == PluginCompatibility::Compatible,
+        spin_compatibility: plugin.manifest.spin_compatibility(),
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PluginListJson {
+    name: String,
+    version: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+    installed: bool,
+    /// Set when a version other than `version` is installed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    installed_version: Option<String>,
+    compatible: bool,
+    spin_compatibility: String,
 }
 
-/// Search for plugins by name.
+/// Search for plugins by name or description.
 #[derive(Parser, Debug)]
 pub struct Search {
-    /// The text to search for. If omitted, all plugins are returned.
+    /// The text to search for, ignoring case. If omitted, all plugins are returned.
     pub filter: Option<String>,
+
+    /// Show at most this many plugins.
+    #[clap(long = "limit")]
+    pub limit: Option<usize>,
+
+    /// The format in which to list the plugins.
+    #[clap(value_enum, long = "format", default_value = "plain")]
+    pub format: OutputFormat,
+
+    /// Print JSON, as a shorthand for `--format json`.
+    #[clap(long = "json", takes_value = false, conflicts_with = "format")]
+    pub json: bool,
 }
 
 impl Search {
     async fn run(&self) -> anyhow::Result<()> {
-        let list_cmd = List {
-            installed: false,
-            all: true,
-            summary: false,
-            filter: self.filter.clone(),
-        };
+        let mut plugins = summarise(list_catalogue_and_installed_plugins().await?);
+
+        plugins.sort_by(|p, q| p.cmp(q));
+
+        if let Some(filter) = self.filter.as_ref() {
+            plugins.retain(|p| p.matches(filter));
+        }
+        if let Some(limit) = self.limit {
+            plugins.truncate(limit);
+        }
 
-        list_cmd.run().await
+        List::print(&plugins, OutputFormat::chosen(&self.format, self.json))
     }
 }
 
+/// Show the details of a plugin in the spin-plugins repository.
+#[derive(Parser, Debug)]
+pub struct Show {
+    /// Name of Spin plugin.
+    pub name: String,
+
+    /// Show this version of the plugin rather than the latest.
+    #[clap(long = "version", short = 'v')]
+    pub version: Option<Version>,
+
+    /// The format in which to show the plugin.
+    #[clap(value_enum, long = "format", default_value = "plain")]
+    pub format: OutputFormat,
+
+    /// Print JSON, as a shorthand for `--format json`.
+    #[clap(long = "json", takes_value = false, conflicts_with = "format")]
+    pub json: bool,
+}
+
+impl Show {
+    async fn run(&self) -> Result<()> {
+        if update_silent().await.is_err() {
+            terminal::warn!("Couldn't update plugins registry cache - using most recent");
+        }
+
+        let manager = PluginManager::try_default()?;
+        let location = ManifestLocation::PluginsRepository(PluginLookup::new(
+            &self.name,
+            self.version.clone(),
+        ));
+        // Showing a plugin that can't be installed here is still useful, so
+        // skip the compatibility check.
+        let manifest = manager.get_manifest(&location, true, SPIN_VERSION).await?;
+        let versions = available_versions(manager.store().catalogue_manifests()?, &manifest.name());
+
+        match OutputFormat::chosen(&self.format, self.json) {
+            OutputFormat::Plain => Self::print_plain(&manifest, &versions),
+            OutputFormat::Json => Self::print_json(&manifest, versions)?,
+        }
+        Ok(())
+    }
+
+    fn print_plain(manifest: &PluginManifest, versions: &[String]) {
+        println!("{} {}", manifest.name(), manifest.version());
+        if let Some(description) = manifest.description() {
+            println!("\nDescription:");
+            println!("\t{description}");
+        }
+        if let Some(homepage) = manifest.homepage_url() {
+            println!("\nHomepage:");
+            println!("\t{homepage}");
+        }
+        println!("\nLicense:");
+        println!("\t{}", manifest.license());
+        println!("\nSpin compatibility:");
+        println!("\t{}", manifest.spin_compatibility());
+        println!("\nPackages:");
+        for package in manifest.packages() {
+            println!("\t{}/{}: {}", package.os(), package.arch(), package.url());
+        }
+        if !versions.is_empty() {
+            println!("\nAvailable versions:");
+            println!("\t{}", versions.join(", "));
+        }
+    }
+
+    fn print_json(manifest: &PluginManifest, available_versions: Vec<String>) -> Result<()> {
+        let json_val = PluginShowJson {
+            manifest,
+            available_versions,
+        };
+        let json_text = serde_json::to_string_pretty(&json_val)?;
+        println!("{}", json_text);
+        Ok(())
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PluginShowJson<'a> {
+    #[serde(flatten)]
+    manifest: &'a PluginManifest,
+    available_versions: Vec<String>,
+}
+
+/// The versions of the plugin named `name` among `manifests`, oldest first.
+fn available_versions(manifests: Vec<PluginManifest>, name: &str) -> Vec<String> {
+    let mut versions: Vec<_> = manifests
+        .into_iter()
+        .filter(|m| m.name() == name)
+        .map(|m| m.version().to_owned())
+        .collect();
+    versions.sort_by(|v, w| match (Version::parse(v), Version::parse(w)) {
+        (Ok(v), Ok(w)) => v.cmp(&w),
+        _ => v.cmp(w),
+    });
+    // The catalogue holds the latest version both under its own version and
+    // as the plugin's default manifest.
+    versions.dedup();
+    versions
+}
+
 #[derive(Debug, PartialEq)]
 pub(crate) enum PluginCompatibility {
     Compatible,
@@ -627,8 +1689,11 @@ pub(crate) enum PluginCompatibility {
 
 impl PluginCompatibility {
     pub(crate) fn for_current(manifest: &PluginManifest) -> Self {
+        Self::for_version(manifest, SPIN_VERSION)
+    }
+
+    fn for_version(manifest: &PluginManifest, spin_version: &str) -> Self {
         if manifest.has_compatible_package() {
-            let spin_version = SPIN_VERSION;
             if manifest.is_compatible_spin_version(spin_version) {
                 Self::Compatible
             } else {
@@ -662,6 +1727,16 @@ impl PluginDescriptor {
 
         self.name.cmp(&other.name).then(version_cmp)
     }
+
+    /// Whether the plugin's name or description contains `query`, ignoring case.
+    fn matches(&self, query: &str) -> bool {
+        let query = query.to_lowercase();
+        self.name.to_lowercase().contains(&query)
+            || self
+                .manifest
+                .description()
+                .is_some_and(|d| d.to_lowercase().contains(&query))
+    }
 }
 
 // Auxiliar function for Upgrade::upgrade_multiselect
@@ -705,6 +1780,18 @@ pub(crate) async fn update() -> Result<()> {
 }
 
 pub(crate) async fn update_silent() -> Result<()> {
+    update_silent_from(None, false, None).await
+}
+
+/// Updates the locally cached spin-plugins repository, first replacing it with
+/// a clone of `repo_url` if given. Otherwise the existing clone is pulled,
+/// unless `force` asks for it to be cloned again. Git connects through `proxy`
+/// if one is given.
+async fn update_silent_from(
+    repo_url: Option<&Url>,
+    force: bool,
+    proxy: Option<&Url>,
+) -> Result<()> {
     let manager = PluginManager::try_default()?;
 
     let mut locker = manager.update_lock().await;
@@ -714,27 +1801,111 @@ pub(crate) async fn update_silent() -> Result<()> {
     }
 
     let plugins_dir = manager.store().get_plugins_directory();
-    let url = plugins_repo_url()?;
-    fetch_plugins_repo(&url, plugins_dir, true).await?;
+    match repo_url {
+        Some(url) => {
+            replace_plugins_repo(url, plugins_dir, proxy).await?;
+            save_plugins_repo_url(plugins_dir, url)?;
+        }
+        None => {
+            let url = configured_plugins_repo_url(plugins_dir)?;
+            match force {
+                true => refresh_plugins_repo(&url, plugins_dir, proxy).await?,
+                false => fetch_plugins_repo(&url, plugins_dir, true, proxy).await?,
+            }
+        }
+    }
     Ok(())
 }
 
+/// Looks up the manifest at `manifest_location`.
+async fn get_manifest(
+    manager: &PluginManager,
+    manifest_location: &ManifestLocation,
+    options: &InstallOptions,
+) -> Result<PluginManifest> {
+    let verbosity = options.verbosity;
+    verbosity.detail(format!(
+        "Looking up the manifest of {}",
+        describe_location(manifest_location)
+    ));
+    let manifest = manager
+        .get_manifest(
+            manifest_location,
+            options.override_compatibility_check,
+            &options.spin_version,
+        )
+        .await?;
+    verbosity.detail(format!(
+        "Found version {} of plugin '{}'",
+        manifest.version(),
+        manifest.name()
+    ));
+    Ok(manifest)
+}
+
+/// Explains why `manifest`, having passed the compatibility check, may be
+/// installed on `spin_version`.
+fn describe_compatibility(manifest: &PluginManifest, spin_version: &str) -> String {
+    let name = manifest.name();
+    let supported = manifest.spin_compatibility();
+    if manifest.is_compatible_spin_version(spin_version) {
+        format!("Plugin '{name}' supports Spin {supported}, which includes Spin {spin_version}")
+    } else {
+        format!("Plugin '{name}' supports Spin {supported}, which doesn't include Spin {spin_version}, but the compatibility check was overridden")
+    }
+}
+
 fn continue_to_install(
     manifest: &PluginManifest,
     package: &PluginPackage,
-    yes_to_all: bool,
+    options: &InstallOptions,
 ) -> Result<bool> {
-    Ok(yes_to_all || prompt_confirm_install(manifest, package)?)
+    let &InstallOptions {
+        yes_to_all,
+        accept_license,
+        verbosity,
+        ..
+    } = options;
+    if accept_license && !verbosity.quiet {
+        println!(
+            "Accepted the {} license of the `{}` plugin",
+            manifest.license(),
+            manifest.name()
+        );
+    }
+    if !yes_to_all {
+        return prompt_confirm_install(manifest, package, accept_license);
+    }
+    if !accept_license && !verbosity.quiet {
+        // `--yes` accepts the license too, so still surface the terms.
+        println!(
+            "Accepted the {} license of the `{}` plugin, as --yes was given",
+            manifest.license(),
+            manifest.name()
+        );
+    }
+    Ok(true)
 }
 
-fn prompt_confirm_install(manifest: &PluginManifest, package: &PluginPackage) -> Result<bool> {
+fn prompt_confirm_install(
+    manifest: &PluginManifest,
+    package: &PluginPackage,
+    accept_license: bool,
+) -> Result<bool> {
     println!(
         "You are trying to install the `{}` plugin with {} license from {} ",
         manifest.name(),
         manifest.license(),
         package.url()
     );
-    let prompt = "Are you sure you want to continue?".to_string();
+    let prompt = if accept_license {
+        "Are you sure you want to continue?".to_string()
+    } else {
+        format!(
+            "Do you accept the {} license and want to continue?",
+            manifest.license()
+        )
+    };
     let install = dialoguer::Confirm::new()
         .with_prompt(prompt)
         .default(true)
@@ -749,41 +1920,71 @@ fn prompt_confirm_install(manifest: &PluginManifest, package: &PluginPackage) ->
 async fn try_install(
     manifest: &PluginManifest,
     manager: &PluginManager,
-    yes_to_all: bool,
-    override_compatibility_check: bool,
-    downgrade: bool,
+    options: &InstallOptions,
     source: &ManifestLocation,
 ) -> Result<bool> {
+    let Some(package) = confirm_install(manifest, manager, options)? else {
+        return Ok(false);
+    };
+
+    let installed = manager.install(manifest, package, source).await?;
+    report_installed(&installed, manifest, options.verbosity);
+    Ok(true)
+}
+
+/// Checks whether `manifest` should be installed, prompting unless
+/// `options.yes_to_all` is set, and returns the package to install if so. The
+/// prompt asks for the license to be accepted unless `options.accept_license`
+/// is set.
+fn confirm_install<'a>(
+    manifest: &'a PluginManifest,
+    manager: &PluginManager,
+    options: &InstallOptions,
+) -> Result<Option<&'a PluginPackage>> {
+    let verbosity = options.verbosity;
     let install_action = manager.check_manifest(
         manifest,
-        SPIN_VERSION,
-        override_compatibility_check,
-        downgrade,
+        &options.spin_version,
+        options.override_compatibility_check,
+        options.downgrade,
     )?;
 
     if let InstallAction::NoAction { name, version } = install_action {
-        eprintln!("Plugin '{name}' is already installed with version {version}.");
-        return Ok(false);
+        if !verbosity.quiet {
+            eprintln!("Plugin '{name}' is already installed with version {version}.");
+        }
+        return Ok(None);
     }
+    verbosity.detail(describe_compatibility(manifest, &options.spin_version));
 
     let package = manager::get_package(manifest)?;
-    if continue_to_install(manifest, package, yes_to_all)? {
-        let installed = manager.install(manifest, package, source).await?;
-        println!("Plugin '{installed}' was installed successfully!");
+    verbosity.detail(format!(
+        "Using the {}/{} package from {}",
+        package.os(),
+        package.arch(),
+        package.url()
+    ));
+    if continue_to_install(manifest, package, options)? {
+        Ok(Some(package))
+    } else {
+        Ok(None)
+    }
+}
 
-        if let Some(description) = manifest.description() {
-            println!("\nDescription:");
-            println!("\t{description}");
-        }
+fn report_installed(installed: &str, manifest: &PluginManifest, verbosity: Verbosity) {
+    if verbosity.quiet {
+        return;
+    }
+    println!("Plugin '{installed}' was installed successfully!");
 
-        if let Some(homepage) = manifest.homepage_url().filter(|h| h.scheme() == "https") {
-            println!("\nHomepage:");
-            println!("\t{homepage}");
-        }
+    if let Some(description) = manifest.description() {
+        println!("\nDescription:");
+        println!("\t{description}");
+    }
 
-        Ok(true)
-    } else {
-        Ok(false)
+    if let Some(homepage) = manifest.homepage_url().filter(|h| h.scheme() == "https") {
+        println!("\nHomepage:");
+        println!("\t{homepage}");
     }
 }
 
@@ -810,6 +2011,187 @@ mod test {
         }
     }
 
+    #[test]
+    fn install_accepts_several_plugins() {
+        let install = Install::try_parse_from(["install", "js2wasm", "py2wasm", "--yes"]).unwrap();
+        assert_eq!(vec!["js2wasm", "py2wasm"], install.name);
+        assert!(install.yes_to_all);
+
+        let install = Install::try_parse_from([
+            "install",
+            "--url",
+            "https://example.com/a.json",
+            "--url",
+            "https://example.com/b.json",
+        ])
+        .unwrap();
+        assert_eq!(2, install.remote_manifest_src.len());
+
+        let install = Install::try_parse_from([
+            "install",
+            "--git",
+            "https://github.com/example/plugin",
+            "--rev",
+            "v1",
+        ])
+        .unwrap();
+        assert!(install.git.is_some());
+        assert!(Install::try_parse_from(["install", "--rev", "v1"]).is_err());
+        assert!(Install::try_parse_from(["install", "--file", "plugin.json", "--lock"]).is_err());
+    }
+
+    #[test]
+    fn quiet_and_verbose_are_exclusive() {
+        let install = Install::try_parse_from(["install", "js2wasm", "--quiet"]).unwrap();
+        assert!(install.verbosity.quiet);
+        assert!(
+            Upgrade::try_parse_from(["upgrade", "--all", "--verbose"])
+                .unwrap()
+                .verbosity
+                .verbose
+        );
+        assert!(Install::try_parse_from(["install", "js2wasm", "--quiet", "--verbose"]).is_err());
+    }
+
+    #[test]
+    fn spin_version_can_be_overridden() {
+        let install =
+            Install::try_parse_from(["install", "js2wasm", "--spin-version", "9.0.0"]).unwrap();
+        assert_eq!("9.0.0", install.install_options(false).spin_version);
+        let install = Install::try_parse_from(["install", "js2wasm"]).unwrap();
+        assert_eq!(SPIN_VERSION, install.install_options(false).spin_version);
+        assert!(Install::try_parse_from(["install", "js2wasm", "--spin-version", "9"]).is_err());
+    }
+
+    #[test]
+    fn compatibility_decisions_are_explained() {
+        let manifest = dummy_descriptor("0.2.0").manifest;
+        assert_eq!(
+            "Plugin 'dummy' supports Spin >= 0.1, which includes Spin 2.0.0",
+            describe_compatibility(&manifest, "2.0.0")
+        );
+        assert!(describe_compatibility(&manifest, "0.0.1").contains("was overridden"));
+    }
+
+    #[test]
+    fn uninstall_takes_names_or_all() {
+        let uninstall = Uninstall::try_parse_from(["uninstall", "js2wasm", "py2wasm"]).unwrap();
+        assert_eq!(vec!["js2wasm", "py2wasm"], uninstall.name);
+        assert!(
+            Uninstall::try_parse_from(["uninstall", "--all", "--yes"])
+                .unwrap()
+                .all
+        );
+        assert!(Uninstall::try_parse_from(["uninstall"]).is_err());
+        assert!(Uninstall::try_parse_from(["uninstall", "js2wasm", "--all"]).is_err());
+    }
+
+    #[test]
+    fn update_status_marks_newer_versions() {
+        let current = dummy_descriptor("0.2.0").manifest;
+        let newer = dummy_descriptor("0.10.0").manifest;
+        let older = dummy_descriptor("0.1.0").manifest;
+        assert_eq!(UPDATE_AVAILABLE, update_status(&current, Some(&newer)));
+        assert_eq!("up to date", update_status(&current, Some(&current)));
+        assert_eq!("up to date", update_status(&current, Some(&older)));
+        assert_eq!("not in repository", update_status(&current, None));
+    }
+
+    #[test]
+    fn failed_lookups_are_unknown() {
+        use spin_plugins::error::{ConnectionFailedError, InvalidManifestError, NotFoundError};
+
+        let current = dummy_descriptor("0.2.0").manifest;
+        let newer = dummy_descriptor("0.10.0").manifest;
+        assert_eq!(
+            ("0.10.0".to_owned(), UPDATE_AVAILABLE),
+            latest_and_status(&current, &Ok(newer))
+        );
+        let missing = Error::NotFound(NotFoundError::new(None, "a".into(), "b".into()));
+        assert_eq!(
+            ("-".to_owned(), "not in repository"),
+            latest_and_status(&current, &Err(missing))
+        );
+        for error in [
+            Error::ConnectionFailed(ConnectionFailedError::new("a".into(), "b".into())),
+            Error::InvalidManifest(InvalidManifestError::new(None, "a".into(), "b".into())),
+        ] {
+            assert_eq!(
+                (UNKNOWN.to_owned(), UNKNOWN),
+                latest_and_status(&current, &Err(error))
+            );
+        }
+    }
+
+    #[test]
+    fn check_updates_accepts_a_spin_version() {
+        let check = CheckUpdates::try_parse_from(["check-updates", "--spin-version", "3.0.0"]);
+        assert_eq!(Some(Version::new(3, 0, 0)), check.unwrap().spin_version);
+    }
+
+    #[test]
+    fn exported_plugins_are_imported_from_where_they_came() -> Result<()> {
+        let export: PluginExport = serde_json::from_value(serde_json::json!({
+            "plugins": [
+                { "name": "js2wasm", "version": "0.6.1" },
+                {
+                    "name": "example",
+                    "version": "1.0.0",
+                    "manifestUrl": "https://example.com/example.json"
+                }
+            ]
+        }))?;
+
+        match export.plugins[0].manifest_location()? {
+            ManifestLocation::PluginsRepository(lookup) => {
+                assert_eq!("js2wasm", lookup.name);
+                assert_eq!(Some(Version::new(0, 6, 1)), lookup.version);
+            }
+            _ => panic!("expected a plugins repository lookup"),
+        }
+        assert!(matches!(
+            export.plugins[1].manifest_location()?,
+            ManifestLocation::Remote(url) if url.as_str() == "https://example.com/example.json"
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn import_accepts_the_install_options() {
+        let import = Import::try_parse_from([
+            "import",
+            "plugins.json",
+            "--no-retry",
+            "--require-signed",
+            "--proxy",
+            "http://proxy.example.com:8080",
+            "--override-compatibility-check",
+            "--spin-version",
+            "9.0.0",
+        ])
+        .unwrap();
+        assert!(import.no_retry && import.require_signed);
+        assert_eq!(
+            Some("http://proxy.example.com:8080/"),
+            import.proxy.as_ref().map(Url::as_str)
+        );
+        let options = import.install_options();
+        assert!(options.override_compatibility_check && options.downgrade);
+        assert_eq!("9.0.0", options.spin_version);
+
+        let import = Import::try_parse_from(["import", "plugins.json"]).unwrap();
+        assert_eq!(SPIN_VERSION, import.install_options().spin_version);
+    }
+
+    #[test]
+    fn exit_codes_distinguish_outcomes() {
+        assert_eq!(EXIT_CODE_NO_CHANGE, failure_exit_code(&NoChange.into()));
+        let incompatible = anyhow::Error::from(IncompatibleError::new("too old"))
+            .context("Failed to upgrade plugin 'example'");
+        assert_eq!(EXIT_CODE_INCOMPATIBLE, failure_exit_code(&incompatible));
+        assert_eq!(1, failure_exit_code(&anyhow!("network down")));
+    }
+
     #[test]
     fn latest_and_rest_if_empty_returns_no_latest_rest_empty() {
         let (latest, rest) = latest_and_rest(vec![]);
@@ -843,4 +2225,74 @@ mod test {
         assert!(rest_vers.contains("1.2.3"));
         assert!(rest_vers.contains("1.3.5"));
     }
+
+    #[test]
+    fn json_is_shorthand_for_format_json() {
+        let list = List::try_parse_from(["list", "--json"]).unwrap();
+        assert!(matches!(
+            OutputFormat::chosen(&list.format, list.json),
+            OutputFormat::Json
+        ));
+        let show = Show::try_parse_from(["show", "js2wasm"]).unwrap();
+        assert!(matches!(
+            OutputFormat::chosen(&show.format, show.json),
+            OutputFormat::Plain
+        ));
+        assert!(Search::try_parse_from(["search", "--json", "--format", "plain"]).is_err());
+    }
+
+    #[test]
+    fn list_json_omits_unset_fields() {
+        let json = serde_json::to_value(json_list_format(&dummy_descriptor("1.2.3"))).unwrap();
+        assert_eq!(
+            serde_json::json!({
+                "name": "dummy",
+                "version": "1.2.3",
+                "installed": false,
+                "compatible": true,
+                "spinCompatibility": ">= 0.1"
+            }),
+            json
+        );
+    }
+
+    #[test]
+    fn available_versions_are_sorted_and_unique() {
+        use serde::Deserialize;
+        let manifests = ["1.10.0", "1.2.3", "1.10.0", "0.9.0"]
+            .into_iter()
+            .map(|v| dummy_descriptor(v).manifest)
+            .chain([PluginManifest::deserialize(serde_json::json!({
+                "name": "other",
+                "version": "2.0.0",
+                "spinCompatibility": ">= 0.1",
+                "license": "dummy",
+                "packages": []
+            }))
+            .unwrap()])
+            .collect();
+        assert_eq!(
+            vec!["0.9.0", "1.2.3", "1.10.0"],
+            available_versions(manifests, "dummy")
+        );
+    }
+
+    #[test]
+    fn search_matches_name_or_description_ignoring_case() {
+        use serde::Deserialize;
+        let mut plugin = dummy_descriptor("1.2.3");
+        plugin.manifest = PluginManifest::deserialize(serde_json::json!({
+            "name": "dummy",
+            "description": "Deploys apps to the Cloud",
+            "version": "1.2.3",
+            "spinCompatibility": ">= 0.1",
+            "license": "dummy",
+            "packages": []
+        }))
+        .unwrap();
+
+        assert!(plugin.matches("DUM"));
+        assert!(plugin.matches("cloud"));
+        assert!(!plugin.matches("kube"));
+    }
 }
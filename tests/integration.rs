@@ -891,6 +891,8 @@ route = "/..."
     #[cfg(not(target_os = "windows"))]
     #[test]
     fn test_spin_plugin_install_command() -> anyhow::Result<()> {
+        use spin_cli::commands::plugins::EXIT_CODE_NO_CHANGE;
+
         let env = test_environment::TestEnvironment::<()>::boot(ServicesConfig::none())?;
 
         let path_to_test_dir = std::env::current_dir()?;
@@ -948,15 +950,24 @@ route = "/..."
         struct Uninstaller<'a>(&'a test_environment::TestEnvironment<()>);
         impl<'a> Drop for Uninstaller<'a> {
             fn drop(&mut self) {
-                let mut uninstall = std::process::Command::new(spin_binary());
-                uninstall.args(["plugins", "uninstall", "example"]);
-                self.0.run_in(&mut uninstall).unwrap();
+                let status = std::process::Command::new(spin_binary())
+                    .args(["plugins", "uninstall", "example"])
+                    .env("SPIN_DATA_DIR", "./plugins")
+                    .current_dir(self.0.path())
+                    .status()
+                    .unwrap();
+                // The plugin may already be gone if the test failed part way.
+                assert!(
+                    status.success() || status.code() == Some(EXIT_CODE_NO_CHANGE),
+                    "Uninstalling the plugin failed: {status}"
+                );
             }
         }
         let _u = Uninstaller(&env);
 
-        let mut install = std::process::Command::new(spin_binary());
-        install
+        // Installing the same version again changes nothing, which is reported
+        // by the exit code.
+        let status = std::process::Command::new(spin_binary())
             .args([
                 "plugins",
                 "install",
@@ -964,9 +975,10 @@ route = "/..."
                 "example-plugin-manifest.json",
                 "--yes",
             ])
-            // Ensure that spin installs the plugins into the temporary directory
-            .env("SPIN_DATA_DIR", "./plugins");
-        env.run_in(&mut install)?;
+            .env("SPIN_DATA_DIR", "./plugins")
+            .current_dir(env.path())
+            .status()?;
+        assert_eq!(Some(EXIT_CODE_NO_CHANGE), status.code());
 
         let mut execute = std::process::Command::new(spin_binary());
         execute.args(["example"]).env("SPIN_DATA_DIR", "./plugins");